@@ -0,0 +1,201 @@
+//! AES Key Wrap of content encryption keys for JWE recipients sharing a key encryption key.
+//!
+//! See https://www.rfc-editor.org/rfc/rfc7518#section-4.4
+use std::collections::BTreeMap;
+
+use aes_kw::{KekAes128, KekAes192, KekAes256};
+use ipld_core::ipld::Ipld;
+
+use crate::{
+    error::Error,
+    jwe::{KeyDecrypter, KeyEncrypter},
+    ProtectedHeader,
+};
+
+/// Wraps and unwraps content encryption keys with a shared AES key encryption key, the
+/// `A128KW`, `A192KW` and `A256KW` algorithms.
+pub enum AesKeyWrap {
+    /// A128KW using a 128 bit key.
+    A128Kw(KekAes128),
+    /// A192KW using a 192 bit key.
+    A192Kw(KekAes192),
+    /// A256KW using a 256 bit key.
+    A256Kw(KekAes256),
+}
+
+impl AesKeyWrap {
+    /// Create a key wrap from the key encryption key, the algorithm is determined by its length
+    /// of 16, 24 or 32 bytes.
+    pub fn new(kek: &[u8]) -> Result<Self, Error> {
+        match kek.len() {
+            16 => Ok(Self::A128Kw(
+                KekAes128::try_from(kek).expect("key is 16 bytes"),
+            )),
+            24 => Ok(Self::A192Kw(
+                KekAes192::try_from(kek).expect("key is 24 bytes"),
+            )),
+            32 => Ok(Self::A256Kw(
+                KekAes256::try_from(kek).expect("key is 32 bytes"),
+            )),
+            _ => Err(Error::InvalidKey("AES key wrap")),
+        }
+    }
+
+    fn wrap(&self, cek: &[u8]) -> Result<Vec<u8>, Error> {
+        match self {
+            Self::A128Kw(kek) => kek.wrap_vec(cek),
+            Self::A192Kw(kek) => kek.wrap_vec(cek),
+            Self::A256Kw(kek) => kek.wrap_vec(cek),
+        }
+        .map_err(|_| Error::Encryption)
+    }
+
+    fn unwrap(&self, encrypted_key: &[u8]) -> Result<Vec<u8>, Error> {
+        match self {
+            Self::A128Kw(kek) => kek.unwrap_vec(encrypted_key),
+            Self::A192Kw(kek) => kek.unwrap_vec(encrypted_key),
+            Self::A256Kw(kek) => kek.unwrap_vec(encrypted_key),
+        }
+        .map_err(|err| match err {
+            aes_kw::Error::IntegrityCheckFailed => Error::KeyUnwrap,
+            _ => Error::InvalidJwe("encrypted key length"),
+        })
+    }
+}
+
+/// Wrap the content encryption key with a 256 bit key encryption key.
+#[cfg(feature = "x25519")]
+pub(crate) fn wrap_a256(kek: &[u8; 32], cek: &[u8]) -> Result<Vec<u8>, Error> {
+    AesKeyWrap::A256Kw(KekAes256::from(*kek)).wrap(cek)
+}
+
+/// Unwrap the content encryption key with a 256 bit key encryption key.
+#[cfg(feature = "x25519")]
+pub(crate) fn unwrap_a256(kek: &[u8; 32], encrypted_key: &[u8]) -> Result<Vec<u8>, Error> {
+    AesKeyWrap::A256Kw(KekAes256::from(*kek)).unwrap(encrypted_key)
+}
+
+impl KeyEncrypter for AesKeyWrap {
+    fn algorithm(&self) -> &str {
+        match self {
+            Self::A128Kw(_) => "A128KW",
+            Self::A192Kw(_) => "A192KW",
+            Self::A256Kw(_) => "A256KW",
+        }
+    }
+
+    fn encrypt_key(
+        &self,
+        cek: &[u8],
+        _header: &mut BTreeMap<String, Ipld>,
+        _tag: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        self.wrap(cek)
+    }
+}
+
+impl KeyDecrypter for AesKeyWrap {
+    fn decrypt_key(
+        &self,
+        header: &ProtectedHeader,
+        encrypted_key: &[u8],
+        _tag: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        match header.alg.as_deref() {
+            Some(alg) if alg == self.algorithm() => self.unwrap(encrypted_key),
+            Some(alg) => Err(Error::UnsupportedAlgorithm(alg.to_string())),
+            None => Err(Error::MissingAlgorithm),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ContentEncryption, JsonWebEncryption, JweBuilder};
+
+    fn kek(len: u8) -> AesKeyWrap {
+        AesKeyWrap::new(&(0..len).collect::<Vec<u8>>()).unwrap()
+    }
+
+    #[test]
+    fn wrap_rfc3394() {
+        // Test vectors from https://www.rfc-editor.org/rfc/rfc3394#section-4
+        let cek = hex::decode("00112233445566778899AABBCCDDEEFF000102030405060708090A0B0C0D0E0F")
+            .unwrap();
+        for (len, cek, wrapped) in [
+            (
+                16,
+                &cek[..16],
+                "1FA68B0A8112B447AEF34BD8FB5A7B829D3E862371D2CFE5",
+            ),
+            (
+                24,
+                &cek[..24],
+                "031D33264E15D33268F24EC260743EDCE1C6C7DDEE725A936BA814915C6762D2",
+            ),
+            (
+                32,
+                &cek[..],
+                "28C9F404C4B810F4CBCCB35CFB87F8263F5786E2D80ED326CBC7F0E71A99F43BFB988B9B7A02DD21",
+            ),
+        ] {
+            let kek = kek(len);
+            let wrapped = hex::decode(wrapped).unwrap();
+            assert_eq!(kek.wrap(cek).unwrap(), wrapped);
+            assert_eq!(kek.unwrap(&wrapped).unwrap(), cek);
+        }
+    }
+
+    #[test]
+    fn unwrap_rfc7516() {
+        // Example from https://www.rfc-editor.org/rfc/rfc7516#appendix-A.3
+        let jwe = JsonWebEncryption::from_compact("eyJhbGciOiJBMTI4S1ciLCJlbmMiOiJBMTI4Q0JDLUhTMjU2In0.6KB707dM9YTIgHtLvtgWQ8mKwboJW3of9locizkDTHzBC2IlrT1oOQ.AxY8DCtDaGlsbGljb3RoZQ.KDlTtXchhZTGufMYmOYGS4HffxPSUrfmqCHXaI9wOGY.U0m_YmjN04DJvceFICbCVQ").unwrap();
+        let kek = AesKeyWrap::new(&base64_url::decode("GawgguFyGrWKav7AX4VKUg").unwrap()).unwrap();
+        let recipient = &jwe.recipients[0];
+        let cek = kek
+            .decrypt_key(
+                &jwe.header(Some(recipient)).unwrap(),
+                recipient.encrypted_key.as_deref().unwrap(),
+                &jwe.tag,
+            )
+            .unwrap();
+        assert_eq!(
+            cek,
+            [
+                4, 211, 31, 197, 84, 157, 252, 254, 11, 100, 157, 250, 63, 170, 106, 206, 107, 124,
+                212, 45, 111, 107, 9, 219, 200, 177, 0, 240, 143, 156, 44, 207
+            ]
+        );
+    }
+
+    #[test]
+    fn roundtrip() {
+        let team = kek(32);
+        let jwe = JweBuilder::new(ContentEncryption::A256Gcm)
+            .recipient(&kek(16))
+            .recipient(&team)
+            .build(b"team secret")
+            .unwrap();
+        assert_eq!(jwe.recipients.len(), 2);
+        assert_eq!(
+            jwe.recipients[1].header.get("alg"),
+            Some(&Ipld::from("A256KW"))
+        );
+        assert_eq!(jwe.decrypt(&team).unwrap(), b"team secret");
+        assert_eq!(jwe.decrypt(&kek(16)).unwrap(), b"team secret");
+        assert!(matches!(
+            jwe.decrypt(&kek(24)),
+            Err(Error::UnsupportedAlgorithm(_))
+        ));
+        // Another key encryption key of the same size fails the integrity check.
+        assert!(matches!(
+            jwe.decrypt(&AesKeyWrap::new(&[7; 32]).unwrap()),
+            Err(Error::KeyUnwrap)
+        ));
+        assert!(matches!(
+            AesKeyWrap::new(&[7; 20]),
+            Err(Error::InvalidKey(_))
+        ));
+    }
+}
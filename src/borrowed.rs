@@ -0,0 +1,304 @@
+//! JOSE values borrowing their fields from a DAG-JOSE block.
+//!
+//! Decoding into the owned types copies each field. The borrowed types instead refer to the
+//! raw bytes of the fields within the block, which avoids allocating when only the link or
+//! headers of a value are inspected. Unprotected headers are decoded into owned IPLD maps.
+use std::collections::BTreeMap;
+
+use ipld_core::{cid::Cid, ipld::Ipld};
+use serde_derive::Deserialize;
+
+use crate::{
+    bytes::Bytes, error::Error, Jose, JsonWebEncryption, JsonWebSignature, ProtectedHeader,
+    Recipient, Signature,
+};
+
+/// The union of the JWS and JWE fields of a DAG-JOSE block, borrowing the byte fields.
+#[derive(Deserialize)]
+struct EncodedRef<'a> {
+    #[serde(borrow)]
+    payload: Option<&'a [u8]>,
+    #[serde(borrow)]
+    signatures: Option<Vec<EncodedSignatureRef<'a>>>,
+    #[serde(borrow)]
+    iv: Option<&'a [u8]>,
+    #[serde(borrow)]
+    aad: Option<&'a [u8]>,
+    #[serde(borrow)]
+    tag: Option<&'a [u8]>,
+    #[serde(borrow)]
+    protected: Option<&'a [u8]>,
+    #[serde(borrow)]
+    ciphertext: Option<&'a [u8]>,
+    #[serde(borrow)]
+    recipients: Option<Vec<EncodedRecipientRef<'a>>>,
+    unprotected: Option<BTreeMap<String, Ipld>>,
+}
+
+#[derive(Deserialize)]
+struct EncodedSignatureRef<'a> {
+    header: Option<BTreeMap<String, Ipld>>,
+    #[serde(borrow)]
+    protected: Option<&'a [u8]>,
+    #[serde(borrow)]
+    signature: &'a [u8],
+}
+
+#[derive(Deserialize)]
+struct EncodedRecipientRef<'a> {
+    header: Option<BTreeMap<String, Ipld>>,
+    #[serde(borrow)]
+    encrypted_key: Option<&'a [u8]>,
+}
+
+/// A JSON Object Signing and Encryption value borrowed from a DAG-JOSE block.
+#[derive(Clone, Debug, PartialEq)]
+pub enum JoseRef<'a> {
+    /// JSON Web Signature value
+    Signature(JwsRef<'a>),
+    /// JSON Web Encryption value
+    Encryption(JweRef<'a>),
+}
+
+impl<'a> JoseRef<'a> {
+    /// Decode a DAG-JOSE block, borrowing the byte fields from it.
+    ///
+    /// As with the owned types, a value with a `payload` is a JWS and any other value a JWE.
+    pub fn from_slice(bytes: &'a [u8]) -> Result<Self, Error> {
+        let encoded: EncodedRef<'a> = serde_ipld_dagcbor::from_slice(bytes)?;
+        Ok(match encoded.payload {
+            Some(payload) => Self::Signature(JwsRef {
+                payload,
+                signatures: encoded
+                    .signatures
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|signature| SignatureRef {
+                        header: signature.header.unwrap_or_default(),
+                        protected: signature.protected,
+                        signature: signature.signature,
+                    })
+                    .collect(),
+            }),
+            None => Self::Encryption(JweRef {
+                aad: encoded.aad,
+                ciphertext: encoded.ciphertext.ok_or(Error::NotJwe)?,
+                iv: encoded.iv.ok_or(Error::NotJwe)?,
+                protected: encoded.protected.ok_or(Error::NotJwe)?,
+                recipients: encoded
+                    .recipients
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|recipient| RecipientRef {
+                        encrypted_key: recipient.encrypted_key,
+                        header: recipient.header.unwrap_or_default(),
+                    })
+                    .collect(),
+                tag: encoded.tag.ok_or(Error::NotJwe)?,
+                unprotected: encoded.unprotected.unwrap_or_default(),
+            }),
+        })
+    }
+}
+
+/// A JSON Web Signature borrowed from a DAG-JOSE block.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JwsRef<'a> {
+    /// The payload, the binary form of the linked CID.
+    pub payload: &'a [u8],
+    /// The set of signatures.
+    pub signatures: Vec<SignatureRef<'a>>,
+}
+
+impl JwsRef<'_> {
+    /// The CID the payload links to.
+    pub fn link(&self) -> Result<Cid, Error> {
+        Ok(Cid::try_from(self.payload)?)
+    }
+}
+
+/// A signature of a JSON Web Signature borrowed from a DAG-JOSE block.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignatureRef<'a> {
+    /// The optional unprotected header.
+    pub header: BTreeMap<String, Ipld>,
+    /// The protected header as JSON.
+    pub protected: Option<&'a [u8]>,
+    /// The signature.
+    pub signature: &'a [u8],
+}
+
+impl SignatureRef<'_> {
+    /// Decode the protected header, if any.
+    pub fn protected_header(&self) -> Result<Option<ProtectedHeader>, Error> {
+        self.protected.map(ProtectedHeader::from_json).transpose()
+    }
+}
+
+/// A JSON Web Encryption borrowed from a DAG-JOSE block.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JweRef<'a> {
+    /// The optional additional authenticated data.
+    pub aad: Option<&'a [u8]>,
+    /// The ciphertext.
+    pub ciphertext: &'a [u8],
+    /// The initialization vector.
+    pub iv: &'a [u8],
+    /// The protected header as JSON.
+    pub protected: &'a [u8],
+    /// The set of recipients.
+    pub recipients: Vec<RecipientRef<'a>>,
+    /// The authentication tag.
+    pub tag: &'a [u8],
+    /// The optional unprotected header.
+    pub unprotected: BTreeMap<String, Ipld>,
+}
+
+impl JweRef<'_> {
+    /// Decode the protected header.
+    ///
+    /// A zero length protected header decodes as an empty header, a block without one is not
+    /// a JWE.
+    pub fn protected_header(&self) -> Result<ProtectedHeader, Error> {
+        if self.protected.is_empty() {
+            Ok(ProtectedHeader::default())
+        } else {
+            ProtectedHeader::from_json(self.protected)
+        }
+    }
+}
+
+/// A recipient of a JSON Web Encryption borrowed from a DAG-JOSE block.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecipientRef<'a> {
+    /// The encrypted content encryption key.
+    pub encrypted_key: Option<&'a [u8]>,
+    /// The optional unprotected header.
+    pub header: BTreeMap<String, Ipld>,
+}
+
+impl<'a> TryFrom<&JoseRef<'a>> for Jose {
+    type Error = Error;
+
+    fn try_from(value: &JoseRef<'a>) -> Result<Self, Self::Error> {
+        Ok(match value {
+            JoseRef::Signature(jws) => Jose::Signature(jws.try_into()?),
+            JoseRef::Encryption(jwe) => Jose::Encryption(jwe.into()),
+        })
+    }
+}
+
+impl<'a> TryFrom<&JwsRef<'a>> for JsonWebSignature {
+    type Error = Error;
+
+    fn try_from(value: &JwsRef<'a>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            link: value.link()?,
+            payload: value.payload.into(),
+            signatures: value
+                .signatures
+                .iter()
+                .map(|signature| Signature {
+                    header: signature.header.clone(),
+                    protected: signature.protected.map(Bytes::from),
+                    signature: signature.signature.into(),
+                })
+                .collect(),
+        })
+    }
+}
+
+impl<'a> From<&JweRef<'a>> for JsonWebEncryption {
+    fn from(value: &JweRef<'a>) -> Self {
+        Self {
+            aad: value.aad.map(Bytes::from),
+            ciphertext: value.ciphertext.into(),
+            iv: value.iv.into(),
+            protected: value.protected.into(),
+            recipients: value
+                .recipients
+                .iter()
+                .map(|recipient| Recipient {
+                    encrypted_key: recipient.encrypted_key.map(Bytes::from),
+                    header: recipient.header.clone(),
+                })
+                .collect(),
+            tag: value.tag.into(),
+            unprotected: value.unprotected.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ipld_core::codec::Codec;
+
+    use super::*;
+    use crate::DagJoseCodec;
+
+    #[test]
+    fn borrow_jws() {
+        let jws = JsonWebSignature::from_compact("eyJhbGciOiJFZERTQSJ9.AXESIIlVZVHDkmZ5zFLHLhgqVhkFakcnQJ7pOibQWtcnyhH0.-_9J5OZcl5lVuRlgI1NJEzc0FqEb6_2yVskUaQPducRQ4oe-N5ynCl57wDm4SPtm1L1bltrphpQeBOeWjVW1BQ").unwrap();
+        let bytes = DagJoseCodec::encode_to_vec(&jws).unwrap();
+        let JoseRef::Signature(borrowed) = JoseRef::from_slice(&bytes).unwrap() else {
+            panic!("not a JWS");
+        };
+        // The fields point into the block.
+        assert!(bytes.as_ptr_range().contains(&borrowed.payload.as_ptr()));
+        assert_eq!(borrowed.link().unwrap(), jws.link);
+        assert_eq!(
+            borrowed.signatures[0]
+                .protected_header()
+                .unwrap()
+                .unwrap()
+                .alg
+                .as_deref(),
+            Some("EdDSA")
+        );
+        assert_eq!(JsonWebSignature::try_from(&borrowed).unwrap(), jws);
+    }
+
+    #[test]
+    fn borrow_jwe() {
+        let jwe = JsonWebEncryption {
+            aad: Some(Bytes::from(b"external".to_vec())),
+            ciphertext: Bytes::from_base64("3XqLW28NHP-raqW8vMfIHOzko4N3IRaR").unwrap(),
+            iv: Bytes::from_base64("PSWIuAyO8CpevzCL").unwrap(),
+            protected: Bytes::from(br#"{"alg":"dir","enc":"A128GCM"}"#.to_vec()),
+            recipients: vec![Recipient {
+                encrypted_key: Some(Bytes::from([1, 2, 3])),
+                header: BTreeMap::from([("kid".to_string(), Ipld::from("k"))]),
+            }],
+            tag: Bytes::from_base64("WZAMBblhzDCsQWOAKdlkSA").unwrap(),
+            unprotected: BTreeMap::from([("x".to_string(), Ipld::from(1))]),
+        };
+        let bytes = DagJoseCodec::encode_to_vec(&jwe).unwrap();
+        let borrowed = JoseRef::from_slice(&bytes).unwrap();
+        let JoseRef::Encryption(ref borrowed_jwe) = borrowed else {
+            panic!("not a JWE");
+        };
+        assert_eq!(
+            borrowed_jwe.recipients[0].encrypted_key,
+            Some(&[1, 2, 3][..])
+        );
+        assert_eq!(
+            borrowed_jwe.protected_header().unwrap().enc.as_deref(),
+            Some("A128GCM")
+        );
+        assert_eq!(Jose::try_from(&borrowed).unwrap(), Jose::Encryption(jwe));
+    }
+
+    #[test]
+    fn reject_incomplete_jwe() {
+        let mut bytes = Vec::new();
+        serde_ipld_dagcbor::to_writer(
+            &mut bytes,
+            &Ipld::Map(BTreeMap::from([(
+                "ciphertext".to_string(),
+                Ipld::Bytes(vec![1, 2]),
+            )])),
+        )
+        .unwrap();
+        assert!(matches!(JoseRef::from_slice(&bytes), Err(Error::NotJwe)));
+    }
+}
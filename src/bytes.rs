@@ -1,17 +1,48 @@
+use std::{fmt, ops::Deref};
+
 use serde::{
     de::{self, Visitor},
     Deserialize, Serialize,
 };
 
+use crate::error::Error;
+
 /// Sequence of byte values.
-#[derive(PartialEq, Default, Debug)]
+///
+/// JOSE values hold their binary fields as raw bytes, the base64 url encoding used by the JSON
+/// serializations is produced on demand by [`Bytes::to_base64`] or the `Display`
+/// implementation.
+#[derive(Clone, PartialEq, Eq, Hash, Default, Debug)]
 pub struct Bytes(Vec<u8>);
 
 impl Bytes {
+    /// Decode base64 url encoded data.
+    pub fn from_base64(data: &str) -> Result<Self, Error> {
+        Ok(Self(base64_url::decode(data)?))
+    }
+
+    /// Encode the bytes using base64 url encoding.
+    pub fn to_base64(&self) -> String {
+        base64_url::encode(&self.0)
+    }
+
+    /// The bytes as a slice.
     pub fn as_slice(&self) -> &[u8] {
         &self.0[..]
     }
+
+    /// Convert into the underlying vector.
+    pub fn into_vec(self) -> Vec<u8> {
+        self.0
+    }
 }
+
+impl fmt::Display for Bytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_base64())
+    }
+}
+
 impl Serialize for Bytes {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -46,14 +77,62 @@ impl<'de> Visitor<'de> for BytesVisitor {
     }
 }
 
+/// Serialize bytes as a base64 url encoded string, as in the DAG-JSON form of JOSE values.
+pub(crate) fn serialize_base64<S>(bytes: &Bytes, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.serialize_str(&bytes.to_base64())
+}
+
+/// Serialize optional bytes as a base64 url encoded string.
+pub(crate) fn serialize_base64_option<S>(
+    bytes: &Option<Bytes>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match bytes {
+        Some(bytes) => serializer.serialize_some(&bytes.to_base64()),
+        None => serializer.serialize_none(),
+    }
+}
+
 impl From<Vec<u8>> for Bytes {
     fn from(value: Vec<u8>) -> Self {
         Self(value)
     }
 }
 
+impl From<&[u8]> for Bytes {
+    fn from(value: &[u8]) -> Self {
+        Self(value.to_vec())
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for Bytes {
+    fn from(value: [u8; N]) -> Self {
+        Self(value.to_vec())
+    }
+}
+
+impl From<Bytes> for Vec<u8> {
+    fn from(value: Bytes) -> Self {
+        value.0
+    }
+}
+
 impl AsRef<[u8]> for Bytes {
     fn as_ref(&self) -> &[u8] {
         self.as_slice()
     }
 }
+
+impl Deref for Bytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
@@ -0,0 +1,306 @@
+//! Reading the structure of DAG-CBOR encoded data without decoding it.
+//!
+//! See https://www.rfc-editor.org/rfc/rfc8949#section-3 and
+//! https://ipld.io/specs/codecs/dag-cbor/spec/
+use std::{io::Read, ops::Range};
+
+use crate::error::Error;
+
+/// Read the head of a CBOR data item, returning its major type, argument and length.
+///
+/// Returns `None` if the head is truncated, reserved or of indefinite length, which DAG-CBOR
+/// does not allow.
+pub(crate) fn head(bytes: &[u8]) -> Option<(u8, u64, usize)> {
+    let initial = *bytes.first()?;
+    let (major, info) = (initial >> 5, initial & 0x1f);
+    match info {
+        0..=23 => Some((major, u64::from(info), 1)),
+        24..=27 => {
+            let len = 1 << (info - 24);
+            let argument = bytes
+                .get(1..1 + len)?
+                .iter()
+                .fold(0, |value, &b| (value << 8) | u64::from(b));
+            Some((major, argument, 1 + len))
+        }
+        _ => None,
+    }
+}
+
+/// The length of the first CBOR data item, or `None` if it is truncated or uses an encoding
+/// not allowed in DAG-CBOR.
+///
+/// Only the item structure is read, the content is validated when decoding.
+pub(crate) fn item_len(bytes: &[u8]) -> Option<usize> {
+    let mut offset = 0;
+    // The number of data items still to be read.
+    let mut pending: u64 = 1;
+    while pending > 0 {
+        pending -= 1;
+        let (major, argument, len) = head(bytes.get(offset..)?)?;
+        offset += len;
+        match major {
+            // Byte and text strings.
+            2 | 3 => {
+                offset = offset.checked_add(usize::try_from(argument).ok()?)?;
+            }
+            // Arrays and maps.
+            4 => pending = pending.checked_add(argument)?,
+            5 => pending = pending.checked_add(argument.checked_mul(2)?)?,
+            // Tags are followed by the tagged item.
+            6 => pending += 1,
+            // Integers, simple values and floats are fully described by their head.
+            _ => {}
+        }
+    }
+    (offset <= bytes.len()).then_some(offset)
+}
+
+/// The path of map keys and list indexes to the innermost data item containing the byte at
+/// `offset`.
+///
+/// The path ends early where the structure cannot be read, such as at a non-string map key.
+pub(crate) fn path_at(bytes: &[u8], offset: usize) -> Vec<String> {
+    let mut path = Vec::new();
+    // The start of the item containing the offset.
+    let mut start = 0;
+    'item: while let Some((major, argument, len)) = bytes.get(start..).and_then(head) {
+        let mut position = start + len;
+        if offset < position {
+            break;
+        }
+        match major {
+            4 => {
+                for index in 0..argument {
+                    let Some(len) = item_len(&bytes[position..]) else {
+                        break 'item;
+                    };
+                    if offset < position + len {
+                        path.push(index.to_string());
+                        start = position;
+                        continue 'item;
+                    }
+                    position += len;
+                }
+                break;
+            }
+            5 => {
+                for _ in 0..argument {
+                    let Some((3, key_len, head_len)) = head(&bytes[position..]) else {
+                        break 'item;
+                    };
+                    let key_start = position + head_len;
+                    let Some(key) = usize::try_from(key_len)
+                        .ok()
+                        .and_then(|len| bytes.get(key_start..key_start.checked_add(len)?))
+                    else {
+                        break 'item;
+                    };
+                    let key_len = head_len + key.len();
+                    let Some(value_len) = item_len(&bytes[position + key_len..]) else {
+                        break 'item;
+                    };
+                    if offset < position + key_len + value_len {
+                        path.push(String::from_utf8_lossy(key).into_owned());
+                        if offset < position + key_len {
+                            break 'item;
+                        }
+                        start = position + key_len;
+                        continue 'item;
+                    }
+                    position += key_len + value_len;
+                }
+                break;
+            }
+            6 => start = position,
+            _ => break,
+        }
+    }
+    path
+}
+
+/// The keys of a top level map along with the ranges of their values, or `None` if the bytes
+/// are not a map with text string keys.
+pub(crate) fn map_fields(bytes: &[u8]) -> Option<Vec<(&[u8], Range<usize>)>> {
+    let (5, len, mut position) = head(bytes)? else {
+        return None;
+    };
+    let mut fields = Vec::new();
+    for _ in 0..len {
+        let (3, _, head_len) = head(&bytes[position..])? else {
+            return None;
+        };
+        let value_start = position + item_len(&bytes[position..])?;
+        let value_end = value_start + item_len(&bytes[value_start..])?;
+        fields.push((
+            &bytes[position + head_len..value_start],
+            value_start..value_end,
+        ));
+        position = value_end;
+    }
+    Some(fields)
+}
+
+/// Append the shortest head of a CBOR data item with the major type and argument.
+pub(crate) fn write_head(major: u8, argument: u64, buffer: &mut Vec<u8>) {
+    let major = major << 5;
+    match argument {
+        0..=23 => buffer.push(major | argument as u8),
+        24..=0xff => buffer.extend([major | 24, argument as u8]),
+        0x100..=0xffff => {
+            buffer.push(major | 25);
+            buffer.extend((argument as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            buffer.push(major | 26);
+            buffer.extend((argument as u32).to_be_bytes());
+        }
+        _ => {
+            buffer.push(major | 27);
+            buffer.extend(argument.to_be_bytes());
+        }
+    }
+}
+
+/// The range of the value of a field of the top level map, or `None` if the bytes are not a
+/// map containing the field.
+pub(crate) fn map_value(bytes: &[u8], field: &str) -> Option<Range<usize>> {
+    let (5, len, mut position) = head(bytes)? else {
+        return None;
+    };
+    for _ in 0..len {
+        let key_len = item_len(&bytes[position..])?;
+        let value_start = position + key_len;
+        let value_end = value_start + item_len(&bytes[value_start..])?;
+        if let Some((3, _, head_len)) = head(&bytes[position..]) {
+            if &bytes[position + head_len..value_start] == field.as_bytes() {
+                return Some(value_start..value_end);
+            }
+        }
+        position = value_end;
+    }
+    None
+}
+
+/// Read the head of a CBOR data item, appending its bytes to the buffer and returning its
+/// major type and argument.
+pub(crate) fn read_head(reader: &mut impl Read, buffer: &mut Vec<u8>) -> Result<(u8, u64), Error> {
+    let start = buffer.len();
+    let mut initial = [0];
+    reader.read_exact(&mut initial)?;
+    buffer.push(initial[0]);
+    let len = match initial[0] & 0x1f {
+        0..=23 => 0,
+        info @ 24..=27 => 1 << (info - 24),
+        _ => return Err(Error::InvalidCbor("indefinite length or reserved item")),
+    };
+    buffer.resize(start + 1 + len, 0);
+    reader.read_exact(&mut buffer[start + 1..])?;
+    let (major, argument, _) = head(&buffer[start..]).expect("the head is complete");
+    Ok((major, argument))
+}
+
+/// Read a complete CBOR data item, appending its bytes to the buffer.
+///
+/// As with [`item_len`] only the item structure is read.
+pub(crate) fn read_item(reader: &mut impl Read, buffer: &mut Vec<u8>) -> Result<(), Error> {
+    let mut pending: u64 = 1;
+    while pending > 0 {
+        pending -= 1;
+        match read_head(reader, buffer)? {
+            (2 | 3, len) if reader.take(len).read_to_end(buffer)? as u64 != len => {
+                return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+            }
+            (4, len) => pending = pending.saturating_add(len),
+            (5, len) => pending = pending.saturating_add(len.saturating_mul(2)),
+            (6, _) => pending += 1,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use ipld_core::{cid::Cid, ipld::Ipld};
+
+    use super::*;
+
+    fn encode(ipld: &Ipld) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        serde_ipld_dagcbor::to_writer(&mut bytes, ipld).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn item_length() {
+        let link =
+            Cid::try_from("bafyreihpzfk3fybyyxfldpg3isttkbgcuuiwkwshdvvscbuzrkb3pgzjvq").unwrap();
+        let bytes = encode(&Ipld::List(vec![
+            Ipld::Link(link),
+            Ipld::Float(1.5),
+            Ipld::Bytes(vec![0; 30]),
+            Ipld::Integer(-300),
+        ]));
+        assert_eq!(item_len(&bytes), Some(bytes.len()));
+        assert_eq!(item_len(&bytes[..bytes.len() - 1]), None);
+        // Indefinite length arrays are not DAG-CBOR.
+        assert_eq!(item_len(&[0x9f, 0x01, 0xff]), None);
+        assert_eq!(item_len(&[0x1b, 0, 0, 0, 0, 0, 0, 0, 1, 0]), Some(9));
+    }
+
+    #[test]
+    fn read_items() {
+        let bytes = encode(&Ipld::Map(BTreeMap::from([
+            ("a".to_string(), Ipld::Bytes(vec![7; 300])),
+            ("bb".to_string(), Ipld::List(vec![Ipld::Integer(1)])),
+        ])));
+        let mut reader = &bytes[..];
+        let mut buffer = Vec::new();
+        read_item(&mut reader, &mut buffer).unwrap();
+        assert_eq!(buffer, bytes);
+        assert!(matches!(
+            read_item(&mut &bytes[..bytes.len() - 1], &mut Vec::new()),
+            Err(Error::Io(_))
+        ));
+
+        let value = map_value(&bytes, "a").unwrap();
+        assert_eq!(&bytes[value.start..value.start + 3], [0x59, 0x01, 0x2c]);
+        assert_eq!(value.end, bytes.len() - 5);
+        assert_eq!(map_value(&bytes, "c"), None);
+
+        for argument in [0, 23, 24, 0xff, 0x100, 0x1_0000, 0x1_0000_0000] {
+            let mut head_bytes = Vec::new();
+            write_head(2, argument, &mut head_bytes);
+            assert_eq!(head(&head_bytes), Some((2, argument, head_bytes.len())));
+            let expected = encode(&Ipld::Integer(argument.into()));
+            assert_eq!(head_bytes[1..], expected[1..]);
+        }
+    }
+
+    #[test]
+    fn path() {
+        // {"a": [1, {"bb": "x"}], "c": 2}
+        let bytes = encode(&Ipld::Map(BTreeMap::from([
+            (
+                "a".to_string(),
+                Ipld::List(vec![
+                    Ipld::Integer(1),
+                    Ipld::Map(BTreeMap::from([("bb".to_string(), Ipld::from("x"))])),
+                ]),
+            ),
+            ("c".to_string(), Ipld::Integer(2)),
+        ])));
+        let path = |offset| path_at(&bytes, offset);
+        assert_eq!(path(0), Vec::<String>::new());
+        // The key of a field.
+        assert_eq!(path(1), ["a"]);
+        assert_eq!(path(3), ["a"]);
+        assert_eq!(path(4), ["a", "0"]);
+        assert_eq!(path(6), ["a", "1", "bb"]);
+        assert_eq!(path(9), ["a", "1", "bb"]);
+        assert_eq!(path(bytes.len() - 1), ["c"]);
+    }
+}
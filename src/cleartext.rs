@@ -0,0 +1,77 @@
+//! DAG-CBOR encoded IPLD cleartext of JWE values.
+//!
+//! The cleartext may be padded with zero bytes to a multiple of a block size, as done by
+//! dag-jose-utils, to hide the exact size of the encrypted node.
+use ipld_core::ipld::Ipld;
+
+use crate::{cbor::item_len, error::Error};
+
+/// Encode the node as DAG-CBOR, padded with zero bytes to a multiple of the block size.
+///
+/// A block size of zero or one adds no padding.
+pub(crate) fn encode(ipld: &Ipld, block_size: usize) -> Result<Vec<u8>, Error> {
+    let mut bytes = Vec::new();
+    serde_ipld_dagcbor::to_writer(&mut bytes, ipld)?;
+    let block_size = block_size.max(1);
+    let padding = (block_size - bytes.len() % block_size) % block_size;
+    bytes.resize(bytes.len() + padding, 0);
+    Ok(bytes)
+}
+
+/// Decode the DAG-CBOR encoded node, accepting trailing zero bytes when `padded` is set.
+pub(crate) fn decode(bytes: &[u8], padded: bool) -> Result<Ipld, Error> {
+    let bytes = match (padded, item_len(bytes)) {
+        (true, Some(len)) => {
+            if bytes[len..].iter().any(|&b| b != 0) {
+                return Err(Error::InvalidJwe("cleartext padding"));
+            }
+            &bytes[..len]
+        }
+        // The decoder reports malformed items and trailing data.
+        _ => bytes,
+    };
+    Ok(serde_ipld_dagcbor::from_reader(bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use ipld_core::cid::Cid;
+
+    use super::*;
+
+    fn node() -> Ipld {
+        let link =
+            Cid::try_from("bafyreihpzfk3fybyyxfldpg3isttkbgcuuiwkwshdvvscbuzrkb3pgzjvq").unwrap();
+        Ipld::Map(BTreeMap::from([
+            ("bytes".to_string(), Ipld::Bytes(vec![0; 30])),
+            ("float".to_string(), Ipld::Float(1.5)),
+            ("link".to_string(), Ipld::Link(link)),
+            (
+                "list".to_string(),
+                Ipld::List(vec![Ipld::Null, Ipld::Bool(true), Ipld::Integer(-300)]),
+            ),
+            ("text".to_string(), Ipld::String("hello".to_string())),
+            // The encoding ends with a zero byte, which must not be taken for padding.
+            ("zero".to_string(), Ipld::Integer(0)),
+        ]))
+    }
+
+    #[test]
+    fn padding() {
+        for block_size in [0, 1, 24, 100] {
+            let bytes = encode(&node(), block_size).unwrap();
+            assert_eq!(bytes.len() % block_size.max(1), 0);
+            assert_eq!(decode(&bytes, true).unwrap(), node());
+        }
+        let mut bytes = encode(&node(), 24).unwrap();
+        assert_ne!(item_len(&bytes), Some(bytes.len()));
+        assert!(decode(&bytes, false).is_err());
+        *bytes.last_mut().unwrap() = 1;
+        assert!(matches!(
+            decode(&bytes, true),
+            Err(Error::InvalidJwe("cleartext padding"))
+        ));
+    }
+}
@@ -7,7 +7,7 @@ use ipld_core::ipld::Ipld;
 use serde_derive::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
-use crate::{bytes::Bytes, error::Error, JsonWebEncryption};
+use crate::{bytes::Bytes, cbor, error::Error, JsonWebEncryption};
 use crate::{Jose, Signature};
 use crate::{JsonWebSignature, Recipient};
 
@@ -36,11 +36,11 @@ pub struct Encoded {
     // This means that these fields are represented as a Bytes type of the raw bytes of the field
     // so they can be DAB-CBOR encoded/decoded as raw bytes not a base64url encoded string..
     //
-    // When we convert the data to a Jose object we construct the base64url encoded string from the
-    // raw bytes.
+    // The Jose objects hold the same raw bytes, so converting between them and this structure
+    // only moves or copies the fields.
     //
-    // The dag-json feature takes advantage of this to encode the Jose structs directly preserving
-    // the base64url encoded string.
+    // The dag-json feature encodes the Jose structs directly, serializing these fields as
+    // base64url encoded strings.
 
     // JWS fields
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -65,12 +65,68 @@ pub struct Encoded {
     unprotected: Option<BTreeMap<String, Ipld>>,
 }
 
+/// Top level fields of a JWS.
+const JWS_FIELDS: [&str; 2] = ["payload", "signatures"];
+/// Top level fields of a JWE.
+const JWE_FIELDS: [&str; 7] = [
+    "aad",
+    "ciphertext",
+    "iv",
+    "protected",
+    "recipients",
+    "tag",
+    "unprotected",
+];
+
+impl Encoded {
+    /// Check the top level fields of a DAG-JOSE block before decoding it.
+    ///
+    /// Unknown fields, values mixing JWS and JWE fields and JWS values with an empty
+    /// `signatures` list are rejected. Only the structure of the block is read.
+    pub(crate) fn check_strict(bytes: &[u8]) -> Result<(), Error> {
+        let Some(fields) = cbor::map_fields(bytes) else {
+            // A map whose structure cannot be read uses an encoding DAG-CBOR does not allow,
+            // such as indefinite lengths. Any other kind of value is rejected when decoded as
+            // `Encoded`.
+            return match bytes.first() {
+                Some(initial) if initial >> 5 == 5 => Err(Error::InvalidCbor("map structure")),
+                _ => Ok(()),
+            };
+        };
+        let name = |field: &[u8]| String::from_utf8_lossy(field).into_owned();
+        let is_jws = |field: &[u8]| JWS_FIELDS.iter().any(|name| name.as_bytes() == field);
+        let is_jwe = |field: &[u8]| JWE_FIELDS.iter().any(|name| name.as_bytes() == field);
+        if let Some((field, _)) = fields
+            .iter()
+            .find(|(field, _)| !is_jws(field) && !is_jwe(field))
+        {
+            return Err(Error::UnknownField(name(field)));
+        }
+        let jws = fields.iter().find(|(field, _)| is_jws(field));
+        let jwe = fields.iter().find(|(field, _)| is_jwe(field));
+        if let (Some((jws, _)), Some((jwe, _))) = (jws, jwe) {
+            return Err(Error::MixedFields {
+                jws: name(jws),
+                jwe: name(jwe),
+            });
+        }
+        // The length of an array is the argument of its head, whichever form encodes it.
+        match fields.iter().find(|(field, _)| *field == b"signatures") {
+            Some((_, value)) if matches!(cbor::head(&bytes[value.clone()]), Some((4, 0, _))) => {
+                Err(Error::EmptySignatures)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
 impl<'a> TryFrom<&'a JsonWebSignature> for Encoded {
     type Error = Error;
 
     fn try_from(value: &'a JsonWebSignature) -> Result<Self, Self::Error> {
+        value.check_link()?;
         Ok(Self {
-            payload: Some(value.payload.decode_base64()?),
+            payload: Some(value.payload.clone()),
             signatures: if value.signatures.is_empty() {
                 None
             } else {
@@ -78,8 +134,8 @@ impl<'a> TryFrom<&'a JsonWebSignature> for Encoded {
                     value
                         .signatures
                         .iter()
-                        .map(EncodedSignature::try_from)
-                        .collect::<Result<Vec<EncodedSignature>, Self::Error>>()?,
+                        .map(EncodedSignature::from)
+                        .collect(),
                 )
             },
             ..Default::default()
@@ -91,45 +147,29 @@ impl TryFrom<Encoded> for JsonWebSignature {
     type Error = Error;
 
     fn try_from(value: Encoded) -> Result<Self, Self::Error> {
-        let link = Cid::try_from(value.payload.as_ref().ok_or(Error::NotJws)?.as_slice())?;
+        let payload = value.payload.ok_or(Error::NotJws)?;
         Ok(Self {
-            payload: value
-                .payload
-                .map(|v| v.encode_base64())
-                .ok_or(Error::NotJws)?,
+            link: Cid::try_from(payload.as_slice())?,
+            payload,
             signatures: value
                 .signatures
                 .unwrap_or_default()
                 .into_iter()
                 .map(Signature::from)
                 .collect(),
-            link,
         })
     }
 }
 
-impl<'a> TryFrom<&'a JsonWebEncryption> for Encoded {
-    type Error = Error;
-
-    fn try_from(value: &'a JsonWebEncryption) -> Result<Self, Self::Error> {
-        Ok(Self {
-            iv: if value.iv.is_empty() {
-                None
-            } else {
-                Some(value.iv.decode_base64()?)
-            },
-            aad: value.aad.as_ref().map(|v| v.decode_base64()).transpose()?,
-            tag: if value.tag.is_empty() {
-                None
-            } else {
-                Some(value.tag.decode_base64()?)
-            },
-            protected: if value.protected.is_empty() {
-                None
-            } else {
-                Some(value.protected.decode_base64()?)
-            },
-            ciphertext: Some(value.ciphertext.decode_base64()?),
+impl<'a> From<&'a JsonWebEncryption> for Encoded {
+    fn from(value: &'a JsonWebEncryption) -> Self {
+        let non_empty = |bytes: &Bytes| (!bytes.is_empty()).then(|| bytes.clone());
+        Self {
+            iv: non_empty(&value.iv),
+            aad: value.aad.clone(),
+            tag: non_empty(&value.tag),
+            protected: non_empty(&value.protected),
+            ciphertext: Some(value.ciphertext.clone()),
             recipients: if value.recipients.is_empty() {
                 None
             } else {
@@ -137,8 +177,8 @@ impl<'a> TryFrom<&'a JsonWebEncryption> for Encoded {
                     value
                         .recipients
                         .iter()
-                        .map(EncodedRecipient::try_from)
-                        .collect::<Result<Vec<EncodedRecipient>, Self::Error>>()?,
+                        .map(EncodedRecipient::from)
+                        .collect(),
                 )
             },
             unprotected: if value.unprotected.is_empty() {
@@ -147,7 +187,7 @@ impl<'a> TryFrom<&'a JsonWebEncryption> for Encoded {
                 Some(value.unprotected.to_owned())
             },
             ..Default::default()
-        })
+        }
     }
 }
 
@@ -156,23 +196,17 @@ impl TryFrom<Encoded> for JsonWebEncryption {
 
     fn try_from(value: Encoded) -> Result<Self, Self::Error> {
         Ok(Self {
-            aad: value.aad.map(|v| v.encode_base64()),
-            ciphertext: value
-                .ciphertext
-                .map(|v| v.encode_base64())
-                .ok_or(Error::NotJwe)?,
-            iv: value.iv.map(|v| v.encode_base64()).ok_or(Error::NotJwe)?,
-            protected: value
-                .protected
-                .map(|v| v.encode_base64())
-                .ok_or(Error::NotJwe)?,
+            aad: value.aad,
+            ciphertext: value.ciphertext.ok_or(Error::NotJwe)?,
+            iv: value.iv.ok_or(Error::NotJwe)?,
+            protected: value.protected.ok_or(Error::NotJwe)?,
             recipients: value
                 .recipients
                 .unwrap_or_default()
                 .into_iter()
                 .map(Recipient::from)
                 .collect(),
-            tag: value.tag.map(|v| v.encode_base64()).ok_or(Error::NotJwe)?,
+            tag: value.tag.ok_or(Error::NotJwe)?,
             unprotected: value.unprotected.unwrap_or_default(),
         })
     }
@@ -183,7 +217,7 @@ impl<'a> TryFrom<&'a Jose> for Encoded {
     fn try_from(value: &'a Jose) -> Result<Self, Self::Error> {
         match value {
             Jose::Signature(jws) => jws.try_into(),
-            Jose::Encryption(jwe) => jwe.try_into(),
+            Jose::Encryption(jwe) => Ok(jwe.into()),
         }
     }
 }
@@ -213,23 +247,17 @@ pub struct EncodedSignature {
     signature: Bytes,
 }
 
-impl<'a> TryFrom<&'a Signature> for EncodedSignature {
-    type Error = Error;
-
-    fn try_from(value: &'a Signature) -> Result<Self, Self::Error> {
-        Ok(Self {
+impl<'a> From<&'a Signature> for EncodedSignature {
+    fn from(value: &'a Signature) -> Self {
+        Self {
             header: if value.header.is_empty() {
                 None
             } else {
                 Some(value.header.to_owned())
             },
-            protected: value
-                .protected
-                .as_ref()
-                .map(|v| v.decode_base64())
-                .transpose()?,
-            signature: value.signature.decode_base64()?,
-        })
+            protected: value.protected.clone(),
+            signature: value.signature.clone(),
+        }
     }
 }
 
@@ -237,8 +265,8 @@ impl From<EncodedSignature> for Signature {
     fn from(value: EncodedSignature) -> Self {
         Self {
             header: value.header.unwrap_or_default(),
-            protected: value.protected.map(|v| v.encode_base64()),
-            signature: value.signature.encode_base64(),
+            protected: value.protected,
+            signature: value.signature,
         }
     }
 }
@@ -256,56 +284,24 @@ pub struct EncodedRecipient {
     encrypted_key: Option<Bytes>,
 }
 
-impl<'a> TryFrom<&'a Recipient> for EncodedRecipient {
-    type Error = Error;
-
-    fn try_from(value: &'a Recipient) -> Result<Self, Self::Error> {
-        Ok(Self {
+impl<'a> From<&'a Recipient> for EncodedRecipient {
+    fn from(value: &'a Recipient) -> Self {
+        Self {
             header: if value.header.is_empty() {
                 None
             } else {
                 Some(value.header.to_owned())
             },
-            encrypted_key: value
-                .encrypted_key
-                .as_ref()
-                .map(|v| v.decode_base64())
-                .transpose()?,
-        })
+            encrypted_key: value.encrypted_key.clone(),
+        }
     }
 }
 
 impl From<EncodedRecipient> for Recipient {
     fn from(value: EncodedRecipient) -> Self {
         Self {
-            encrypted_key: value.encrypted_key.map(|v| v.encode_base64()),
+            encrypted_key: value.encrypted_key,
             header: value.header.unwrap_or_default(),
         }
     }
 }
-
-/// Decode base64 url encoded data from Self into T.
-trait DecodeBase64<T: From<Vec<u8>>>: AsRef<[u8]> {
-    type Error: From<base64_url::base64::DecodeError>;
-
-    fn decode_base64(&self) -> Result<T, Self::Error> {
-        Ok(T::from(base64_url::decode(self.as_ref())?))
-    }
-}
-
-impl DecodeBase64<Bytes> for String {
-    type Error = Error;
-}
-impl<'a> DecodeBase64<Bytes> for &'a str {
-    type Error = Error;
-}
-
-/// Encode data from Self into a base64 url encoded string.
-trait EncodeBase64: AsRef<[u8]> {
-    /// Encode value using base64 url encoding.
-    fn encode_base64(&self) -> String {
-        base64_url::encode(self.as_ref())
-    }
-}
-
-impl EncodeBase64 for Bytes {}
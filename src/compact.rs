@@ -0,0 +1,253 @@
+//! Conversion to and from the JOSE Compact Serialization.
+//!
+//! See https://www.rfc-editor.org/rfc/rfc7515#section-7.1 and
+//! https://www.rfc-editor.org/rfc/rfc7516#section-7.1
+use std::collections::BTreeMap;
+
+use ipld_core::cid::Cid;
+
+use crate::{
+    bytes::Bytes, error::Error, JsonWebEncryption, JsonWebSignature, Recipient, Signature,
+};
+
+impl JsonWebSignature {
+    /// Parse a JWS from its compact serialization, `protected.payload.signature`.
+    ///
+    /// The payload must be a CID.
+    pub fn from_compact(compact: &str) -> Result<Self, Error> {
+        let [protected, payload, signature] = split_parts(compact)?;
+        if protected.is_empty() {
+            return Err(Error::InvalidCompact("missing protected header"));
+        }
+        let payload = Bytes::from_base64(payload)?;
+        Ok(Self {
+            link: Cid::try_from(payload.as_slice())?,
+            payload,
+            signatures: vec![Signature {
+                header: BTreeMap::new(),
+                protected: Some(Bytes::from_base64(protected)?),
+                signature: Bytes::from_base64(signature)?,
+            }],
+        })
+    }
+
+    /// Serialize the JWS using its compact serialization, `protected.payload.signature`.
+    ///
+    /// Only a JWS with a single signature, a protected header and no unprotected header can be
+    /// represented in the compact serialization.
+    pub fn to_compact(&self) -> Result<String, Error> {
+        self.check_link()?;
+        let signature = match self.signatures.as_slice() {
+            [signature] => signature,
+            [] => return Err(Error::NotCompact("no signatures")),
+            _ => return Err(Error::NotCompact("multiple signatures")),
+        };
+        if !signature.header.is_empty() {
+            return Err(Error::NotCompact("unprotected header"));
+        }
+        let protected = signature
+            .protected
+            .as_ref()
+            .ok_or(Error::NotCompact("missing protected header"))?;
+        Ok(format!(
+            "{}.{}.{}",
+            protected, self.payload, signature.signature
+        ))
+    }
+}
+
+impl JsonWebEncryption {
+    /// Parse a JWE from its compact serialization,
+    /// `protected.encrypted_key.iv.ciphertext.tag`.
+    ///
+    /// An empty encrypted key, as used by direct encryption, produces a JWE without recipients.
+    pub fn from_compact(compact: &str) -> Result<Self, Error> {
+        let [protected, encrypted_key, iv, ciphertext, tag] = split_parts(compact)?;
+        if protected.is_empty() {
+            return Err(Error::InvalidCompact("missing protected header"));
+        }
+        Ok(Self {
+            aad: None,
+            ciphertext: Bytes::from_base64(ciphertext)?,
+            iv: Bytes::from_base64(iv)?,
+            protected: Bytes::from_base64(protected)?,
+            recipients: if encrypted_key.is_empty() {
+                vec![]
+            } else {
+                vec![Recipient {
+                    encrypted_key: Some(Bytes::from_base64(encrypted_key)?),
+                    header: BTreeMap::new(),
+                }]
+            },
+            tag: Bytes::from_base64(tag)?,
+            unprotected: BTreeMap::new(),
+        })
+    }
+
+    /// Serialize the JWE using its compact serialization,
+    /// `protected.encrypted_key.iv.ciphertext.tag`.
+    ///
+    /// Only a JWE with at most one recipient, no additional authenticated data and no unprotected
+    /// headers can be represented in the compact serialization.
+    pub fn to_compact(&self) -> Result<String, Error> {
+        if self.aad.is_some() {
+            return Err(Error::NotCompact("additional authenticated data"));
+        }
+        if !self.unprotected.is_empty() {
+            return Err(Error::NotCompact("unprotected header"));
+        }
+        let encrypted_key = match self.recipients.as_slice() {
+            [] => String::new(),
+            [recipient] => {
+                if !recipient.header.is_empty() {
+                    return Err(Error::NotCompact("recipient header"));
+                }
+                recipient
+                    .encrypted_key
+                    .as_ref()
+                    .map(Bytes::to_base64)
+                    .unwrap_or_default()
+            }
+            _ => return Err(Error::NotCompact("multiple recipients")),
+        };
+        Ok(format!(
+            "{}.{}.{}.{}.{}",
+            self.protected, encrypted_key, self.iv, self.ciphertext, self.tag
+        ))
+    }
+}
+
+/// Split a compact serialization into exactly N parts.
+fn split_parts<const N: usize>(compact: &str) -> Result<[&str; N], Error> {
+    let mut parts = [""; N];
+    let mut split = compact.split('.');
+    for part in parts.iter_mut() {
+        *part = split.next().ok_or(Error::InvalidCompact("too few parts"))?;
+    }
+    if split.next().is_some() {
+        return Err(Error::InvalidCompact("too many parts"));
+    }
+    Ok(parts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JWS: &str = "eyJhbGciOiJFZERTQSJ9.AXESIIlVZVHDkmZ5zFLHLhgqVhkFakcnQJ7pOibQWtcnyhH0.-_9J5OZcl5lVuRlgI1NJEzc0FqEb6_2yVskUaQPducRQ4oe-N5ynCl57wDm4SPtm1L1bltrphpQeBOeWjVW1BQ";
+
+    #[test]
+    fn roundtrip_jws() {
+        let jws = JsonWebSignature::from_compact(JWS).unwrap();
+        assert_eq!(
+            jws.link.to_string(),
+            "bafyreiejkvsvdq4smz44yuwhfymcuvqzavveoj2at3utujwqlllspsqr6q"
+        );
+        assert_eq!(jws.signatures.len(), 1);
+        assert_eq!(
+            jws.signatures[0].protected.as_deref(),
+            Some(&br#"{"alg":"EdDSA"}"#[..])
+        );
+        assert_eq!(jws.to_compact().unwrap(), JWS);
+    }
+
+    #[test]
+    fn invalid_jws() {
+        assert!(matches!(
+            JsonWebSignature::from_compact("a.b"),
+            Err(Error::InvalidCompact(_))
+        ));
+        assert!(matches!(
+            JsonWebSignature::from_compact(&format!("{JWS}.d")),
+            Err(Error::InvalidCompact(_))
+        ));
+        // Payload is not a CID
+        assert!(matches!(
+            JsonWebSignature::from_compact("eyJhbGciOiJFZERTQSJ9.aGVsbG8.c2ln"),
+            Err(Error::InvalidCid(_))
+        ));
+        // Parts must be valid base64 url data.
+        assert!(matches!(
+            JsonWebSignature::from_compact(
+                "eyJhbGciOiJFZERTQSJ9.AXESIIlVZVHDkmZ5zFLHLhgqVhkFakcnQJ7pOibQWtcnyhH0.c2l*"
+            ),
+            Err(Error::InvalidBase64Url(_))
+        ));
+    }
+
+    #[test]
+    fn not_compact_jws() {
+        let mut jws = JsonWebSignature::from_compact(JWS).unwrap();
+        jws.signatures[0]
+            .header
+            .insert("kid".to_string(), "key".into());
+        assert!(matches!(jws.to_compact(), Err(Error::NotCompact(_))));
+
+        jws.signatures[0].header.clear();
+        jws.signatures.push(jws.signatures[0].clone());
+        assert!(matches!(jws.to_compact(), Err(Error::NotCompact(_))));
+
+        jws.signatures.clear();
+        assert!(matches!(jws.to_compact(), Err(Error::NotCompact(_))));
+    }
+
+    #[test]
+    fn roundtrip_jwe() {
+        // Example from https://www.rfc-editor.org/rfc/rfc7516#appendix-A.3
+        let compact = "eyJhbGciOiJBMTI4S1ciLCJlbmMiOiJBMTI4Q0JDLUhTMjU2In0.6KB707dM9YTIgHtLvtgWQ8mKwboJW3of9locizkDTHzBC2IlrT1oOQ.AxY8DCtDaGlsbGljb3RoZQ.KDlTtXchhZTGufMYmOYGS4HffxPSUrfmqCHXaI9wOGY.U0m_YmjN04DJvceFICbCVQ";
+        let jwe = JsonWebEncryption::from_compact(compact).unwrap();
+        assert_eq!(
+            jwe.protected.as_slice(),
+            br#"{"alg":"A128KW","enc":"A128CBC-HS256"}"#
+        );
+        assert_eq!(
+            jwe.recipients,
+            vec![Recipient {
+                encrypted_key: Some(
+                    Bytes::from_base64("6KB707dM9YTIgHtLvtgWQ8mKwboJW3of9locizkDTHzBC2IlrT1oOQ")
+                        .unwrap()
+                ),
+                header: BTreeMap::new(),
+            }]
+        );
+        assert_eq!(jwe.to_compact().unwrap(), compact);
+
+        // Direct encryption has an empty encrypted key
+        let compact = "eyJhbGciOiJkaXIiLCJlbmMiOiJBMTI4R0NNIn0..PSWIuAyO8CpevzCL.3XqLW28NHP-raqW8vMfIHOzko4N3IRaR.WZAMBblhzDCsQWOAKdlkSA";
+        let jwe = JsonWebEncryption::from_compact(compact).unwrap();
+        assert!(jwe.recipients.is_empty());
+        assert_eq!(jwe.to_compact().unwrap(), compact);
+    }
+
+    #[test]
+    fn not_compact_jwe() {
+        let compact = "eyJhbGciOiJkaXIiLCJlbmMiOiJBMTI4R0NNIn0..PSWIuAyO8CpevzCL.3XqLW28NHP-raqW8vMfIHOzko4N3IRaR.WZAMBblhzDCsQWOAKdlkSA";
+        let jwe = JsonWebEncryption::from_compact(compact).unwrap();
+
+        let mut aad = jwe.clone();
+        aad.aad = Some(Bytes::from(b"aad".to_vec()));
+        assert!(matches!(aad.to_compact(), Err(Error::NotCompact(_))));
+
+        let mut unprotected = jwe.clone();
+        unprotected
+            .unprotected
+            .insert("jku".to_string(), "https://example.com".into());
+        assert!(matches!(
+            unprotected.to_compact(),
+            Err(Error::NotCompact(_))
+        ));
+
+        let mut recipients = jwe;
+        recipients.recipients = vec![
+            Recipient {
+                encrypted_key: Some(Bytes::from(b"key0".to_vec())),
+                header: BTreeMap::new(),
+            },
+            Recipient {
+                encrypted_key: Some(Bytes::from(b"key1".to_vec())),
+                header: BTreeMap::new(),
+            },
+        ];
+        assert!(matches!(recipients.to_compact(), Err(Error::NotCompact(_))));
+    }
+}
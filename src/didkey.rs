@@ -0,0 +1,273 @@
+//! Offline resolution of `did:key` identifiers to public keys.
+//!
+//! The method specific identifier is the base58btc multibase encoding of the multicodec
+//! prefixed public key, the key id of a verification method repeats it as the fragment.
+//!
+//! See https://w3c-ccg.github.io/did-method-key/
+use std::sync::Arc;
+
+use multibase::Base;
+
+use crate::{
+    error::Error,
+    multicodec::{self, ED25519_PUB, P256_PUB, SECP256K1_PUB, X25519_PUB},
+    JsonWebSignature, Jwk, KeyReference, KeyResolver, Verifier,
+};
+
+const DID_KEY: &str = "did:key:";
+
+/// A public key identified by a `did:key` identifier.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DidKey {
+    /// An Ed25519 signature verification key.
+    Ed25519([u8; 32]),
+    /// A secp256k1 signature verification key, SEC1 compressed.
+    Secp256k1([u8; 33]),
+    /// A P-256 signature verification key, SEC1 compressed.
+    P256([u8; 33]),
+    /// An X25519 key agreement key.
+    X25519([u8; 32]),
+}
+
+impl DidKey {
+    /// Resolve a `did:key:z...` identifier.
+    pub fn from_did(did: &str) -> Result<Self, Error> {
+        let id = did
+            .strip_prefix(DID_KEY)
+            .ok_or(Error::InvalidDidKey("not a did:key identifier"))?;
+        let (base, bytes) =
+            multibase::decode(id).map_err(|_| Error::InvalidDidKey("invalid multibase"))?;
+        if base != Base::Base58Btc {
+            return Err(Error::InvalidDidKey("not base58btc encoded"));
+        }
+        Self::from_multicodec(&bytes)
+    }
+
+    fn from_multicodec(bytes: &[u8]) -> Result<Self, Error> {
+        let (codec, public) =
+            multicodec::split(bytes).ok_or(Error::InvalidDidKey("invalid multicodec"))?;
+        let invalid = || Error::InvalidDidKey("invalid public key length");
+        match codec {
+            ED25519_PUB => Ok(Self::Ed25519(public.try_into().map_err(|_| invalid())?)),
+            SECP256K1_PUB => Ok(Self::Secp256k1(public.try_into().map_err(|_| invalid())?)),
+            P256_PUB => Ok(Self::P256(public.try_into().map_err(|_| invalid())?)),
+            X25519_PUB => Ok(Self::X25519(public.try_into().map_err(|_| invalid())?)),
+            codec => Err(Error::UnsupportedMulticodec(codec)),
+        }
+    }
+
+    /// Resolve a key id of the form `did:key:z...#z...`, as used in the `kid` header parameter.
+    ///
+    /// A key id without fragment is resolved as the identifier itself.
+    pub fn from_kid(kid: &str) -> Result<Self, Error> {
+        match kid.split_once('#') {
+            Some((did, fragment)) => {
+                if did.strip_prefix(DID_KEY) != Some(fragment) {
+                    return Err(Error::InvalidDidKey("fragment does not match the key"));
+                }
+                Self::from_did(did)
+            }
+            None => Self::from_did(kid),
+        }
+    }
+
+    /// The `did:key` identifier of the key.
+    pub fn did(&self) -> String {
+        let (codec, public) = match self {
+            Self::Ed25519(public) => (ED25519_PUB, &public[..]),
+            Self::Secp256k1(public) => (SECP256K1_PUB, &public[..]),
+            Self::P256(public) => (P256_PUB, &public[..]),
+            Self::X25519(public) => (X25519_PUB, &public[..]),
+        };
+        format!(
+            "{}{}",
+            DID_KEY,
+            multibase::encode(Base::Base58Btc, multicodec::prefix(codec, public))
+        )
+    }
+
+    /// The key id of the verification method of the key, `did:key:z...#z...`.
+    pub fn kid(&self) -> String {
+        let did = self.did();
+        let fragment = did[DID_KEY.len()..].to_string();
+        format!("{}#{}", did, fragment)
+    }
+
+    /// A verifier for signatures made with the key.
+    ///
+    /// The verifier of a key type is only available when the feature of its algorithm is
+    /// enabled, `UnsupportedAlgorithm` is returned otherwise.
+    pub fn verifier(&self) -> Result<Arc<dyn Verifier + Send + Sync>, Error> {
+        match self {
+            #[cfg(feature = "ed25519")]
+            Self::Ed25519(public) => Ok(Arc::new(crate::Ed25519Verifier::from_bytes(public)?)),
+            #[cfg(not(feature = "ed25519"))]
+            Self::Ed25519(_) => Err(Error::UnsupportedAlgorithm("EdDSA".to_string())),
+            #[cfg(feature = "es256k")]
+            Self::Secp256k1(public) => {
+                Ok(Arc::new(crate::Es256kVerifier::from_sec1_bytes(public)?))
+            }
+            #[cfg(not(feature = "es256k"))]
+            Self::Secp256k1(_) => Err(Error::UnsupportedAlgorithm("ES256K".to_string())),
+            #[cfg(feature = "es256")]
+            Self::P256(public) => Ok(Arc::new(crate::EcdsaVerifier::from_sec1_bytes(
+                "ES256", public,
+            )?)),
+            #[cfg(not(feature = "es256"))]
+            Self::P256(_) => Err(Error::UnsupportedAlgorithm("ES256".to_string())),
+            Self::X25519(_) => Err(Error::KeyUsage {
+                key: "X25519",
+                usage: "signature verification",
+            }),
+        }
+    }
+
+    /// The public key for ECDH key agreement with a JWE recipient.
+    pub fn key_agreement_key(&self) -> Result<[u8; 32], Error> {
+        match self {
+            Self::X25519(public) => Ok(*public),
+            _ => Err(Error::KeyUsage {
+                key: self.key_type(),
+                usage: "key agreement",
+            }),
+        }
+    }
+
+    fn key_type(&self) -> &'static str {
+        match self {
+            Self::Ed25519(_) => "Ed25519",
+            Self::Secp256k1(_) => "secp256k1",
+            Self::P256(_) => "P-256",
+            Self::X25519(_) => "X25519",
+        }
+    }
+}
+
+impl TryFrom<&Jwk> for DidKey {
+    type Error = Error;
+
+    fn try_from(jwk: &Jwk) -> Result<Self, Self::Error> {
+        Self::from_multicodec(&jwk.to_multicodec()?)
+    }
+}
+
+/// Resolves the `kid` of signatures as a `did:key` key id without network access.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DidKeyResolver;
+
+impl KeyResolver for DidKeyResolver {
+    fn resolve(&self, key: &KeyReference) -> Result<Arc<dyn Verifier + Send + Sync>, Error> {
+        DidKey::from_kid(key.kid.as_deref().ok_or(Error::MissingHeader("kid"))?)?.verifier()
+    }
+}
+
+impl JsonWebSignature {
+    /// Verify each signature using the `did:key` identified by its `kid` parameter.
+    ///
+    /// The results are returned in the same order as `signatures`.
+    pub fn verify_did_key(&self) -> Vec<Result<(), Error>> {
+        self.verify_with_resolver(&DidKeyResolver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_spec_examples() {
+        // Examples from https://w3c-ccg.github.io/did-method-key/
+        for (did, key_type) in [
+            (
+                "did:key:z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp",
+                "Ed25519",
+            ),
+            (
+                "did:key:zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme",
+                "secp256k1",
+            ),
+            (
+                "did:key:zDnaerDaTF5BXEavCrfRZEk316dpbLsfPDZ3WJ5hRTPFU2169",
+                "P-256",
+            ),
+            (
+                "did:key:z6LSeu9HkTHSfLLeUs2nnzUSNedgDUevfNQgQjQC23ZCit6F",
+                "X25519",
+            ),
+        ] {
+            let key = DidKey::from_did(did).unwrap();
+            assert_eq!(key.key_type(), key_type);
+            assert_eq!(key.did(), did);
+            assert_eq!(DidKey::from_kid(&key.kid()).unwrap(), key);
+        }
+    }
+
+    #[test]
+    fn reject_invalid() {
+        let kid = "did:key:z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp#z6LSeu9HkTHSfLLeUs2nnzUSNedgDUevfNQgQjQC23ZCit6F";
+        assert!(matches!(
+            DidKey::from_kid(kid),
+            Err(Error::InvalidDidKey(_))
+        ));
+        assert!(matches!(
+            DidKey::from_did("did:web:example.com"),
+            Err(Error::InvalidDidKey(_))
+        ));
+        let did = |codec, public: &[u8]| {
+            let bytes = multicodec::prefix(codec, public);
+            format!("{}{}", DID_KEY, multibase::encode(Base::Base58Btc, bytes))
+        };
+        assert!(matches!(
+            DidKey::from_did(&did(ED25519_PUB, &[7; 31])),
+            Err(Error::InvalidDidKey("invalid public key length"))
+        ));
+        assert!(matches!(
+            DidKey::from_did(&did(0x1205, &[7; 32])),
+            Err(Error::UnsupportedMulticodec(0x1205))
+        ));
+        assert!(matches!(
+            DidKey::X25519([9; 32]).verifier(),
+            Err(Error::KeyUsage { key: "X25519", .. })
+        ));
+        assert!(matches!(
+            DidKey::Ed25519([9; 32]).key_agreement_key(),
+            Err(Error::KeyUsage { key: "Ed25519", .. })
+        ));
+    }
+
+    #[cfg(feature = "ed25519")]
+    #[test]
+    fn verify_did_key() {
+        use crate::{Ed25519Signer, JwsBuilder, ProtectedHeader};
+
+        let signer = Ed25519Signer::from_bytes(&[1; 32]);
+        let public = DidKey::Ed25519(
+            ed25519_dalek::SigningKey::from_bytes(&[1; 32])
+                .verifying_key()
+                .to_bytes(),
+        );
+        let other = DidKey::Ed25519(
+            ed25519_dalek::SigningKey::from_bytes(&[2; 32])
+                .verifying_key()
+                .to_bytes(),
+        );
+        let header = |key: &DidKey| ProtectedHeader {
+            kid: Some(key.kid()),
+            ..Default::default()
+        };
+        let jws = JwsBuilder::new(
+            "bafyreiejkvsvdq4smz44yuwhfymcuvqzavveoj2at3utujwqlllspsqr6q"
+                .parse()
+                .unwrap(),
+        )
+        .signer(&signer, header(&public))
+        .signer(&signer, header(&other))
+        .signer(&signer, ProtectedHeader::default())
+        .build()
+        .unwrap();
+        let results = jws.verify_did_key();
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(Error::InvalidSignature)));
+        assert!(matches!(results[2], Err(Error::MissingHeader("kid"))));
+    }
+}
@@ -0,0 +1,491 @@
+//! ECDH-ES and ECDH-1PU key agreement with X25519 keys for JWE recipients.
+//!
+//! The agreed key wraps the content encryption key using XChaCha20-Poly1305, `ECDH-ES+XC20PKW`
+//! and `ECDH-1PU+XC20PKW`, or AES Key Wrap, `ECDH-ES+A256KW` and `ECDH-1PU+A256KW`.
+//!
+//! ECDH-1PU additionally agrees a secret between the static keys of the sender and recipient,
+//! authenticating the sender identified by the `skid` header parameter. The authentication tag
+//! of the content is bound into its key derivation.
+//!
+//! See https://www.rfc-editor.org/rfc/rfc7518#section-4.6,
+//! https://www.rfc-editor.org/rfc/rfc8037#section-3.2 and
+//! https://datatracker.ietf.org/doc/html/draft-madden-jose-ecdh-1pu-04
+use std::collections::BTreeMap;
+
+use ipld_core::ipld::Ipld;
+use sha2::{Digest, Sha256};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use crate::{
+    aeskw,
+    error::Error,
+    jwe::{random_bytes, ContentEncryption, KeyDecrypter, KeyEncrypter},
+    Jwk, ProtectedHeader,
+};
+
+/// A key agreement with key wrapping algorithm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Algorithm {
+    /// Whether the sender is authenticated using ECDH-1PU.
+    authenticated: bool,
+    wrap: KeyWrap,
+}
+
+impl Algorithm {
+    fn from_name(alg: &str) -> Result<Self, Error> {
+        let (authenticated, wrap) = match alg {
+            "ECDH-ES+XC20PKW" => (false, KeyWrap::Xc20pKw),
+            "ECDH-ES+A256KW" => (false, KeyWrap::A256Kw),
+            "ECDH-1PU+XC20PKW" => (true, KeyWrap::Xc20pKw),
+            "ECDH-1PU+A256KW" => (true, KeyWrap::A256Kw),
+            alg => return Err(Error::UnsupportedAlgorithm(alg.to_string())),
+        };
+        Ok(Self {
+            authenticated,
+            wrap,
+        })
+    }
+
+    fn name(self) -> &'static str {
+        match (self.authenticated, self.wrap) {
+            (false, KeyWrap::Xc20pKw) => "ECDH-ES+XC20PKW",
+            (false, KeyWrap::A256Kw) => "ECDH-ES+A256KW",
+            (true, KeyWrap::Xc20pKw) => "ECDH-1PU+XC20PKW",
+            (true, KeyWrap::A256Kw) => "ECDH-1PU+A256KW",
+        }
+    }
+}
+
+/// The algorithm wrapping the content encryption key with the agreed key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum KeyWrap {
+    Xc20pKw,
+    A256Kw,
+}
+
+impl KeyWrap {
+    /// Wrap the content encryption key, adding any parameters needed to unwrap it to the header.
+    fn wrap(
+        self,
+        kek: &[u8; 32],
+        cek: &[u8],
+        header: &mut BTreeMap<String, Ipld>,
+    ) -> Result<Vec<u8>, Error> {
+        match self {
+            Self::Xc20pKw => {
+                let iv = random_bytes(ContentEncryption::XC20P.iv_len())?;
+                let (encrypted_key, tag) = ContentEncryption::XC20P.encrypt(kek, &iv, &[], cek)?;
+                header.insert("iv".to_string(), Ipld::String(base64_url::encode(&iv)));
+                header.insert("tag".to_string(), Ipld::String(base64_url::encode(&tag)));
+                Ok(encrypted_key)
+            }
+            Self::A256Kw => aeskw::wrap_a256(kek, cek),
+        }
+    }
+
+    fn unwrap(
+        self,
+        kek: &[u8; 32],
+        encrypted_key: &[u8],
+        header: &ProtectedHeader,
+    ) -> Result<Vec<u8>, Error> {
+        match self {
+            Self::Xc20pKw => {
+                let mut cek = encrypted_key.to_vec();
+                ContentEncryption::XC20P.decrypt_in_place(
+                    kek,
+                    &header_bytes(header, "iv")?.ok_or(Error::MissingHeader("iv"))?,
+                    &[],
+                    &mut cek,
+                    &header_bytes(header, "tag")?.ok_or(Error::MissingHeader("tag"))?,
+                )?;
+                Ok(cek)
+            }
+            Self::A256Kw => aeskw::unwrap_a256(kek, encrypted_key),
+        }
+    }
+}
+
+/// Encrypts content encryption keys for an X25519 public key using key agreement with a new
+/// ephemeral key for each recipient.
+///
+/// Each recipient has its own ephemeral key in its recipient header.
+pub struct X25519Encrypter {
+    alg: Algorithm,
+    public: PublicKey,
+    sender: Option<(String, StaticSecret)>,
+    apu: Option<Vec<u8>>,
+    apv: Option<Vec<u8>>,
+}
+
+impl X25519Encrypter {
+    /// Create an anonymous encrypter for the algorithm, `ECDH-ES+XC20PKW` or `ECDH-ES+A256KW`,
+    /// and the 32 byte public key of the recipient.
+    ///
+    /// ECDH-1PU algorithms require the key of the sender, see [`Self::authenticated`], and are
+    /// an `UnsupportedAlgorithm` here.
+    pub fn new(alg: &str, public: &[u8; 32]) -> Result<Self, Error> {
+        let alg = Algorithm::from_name(alg)?;
+        if alg.authenticated {
+            return Err(Error::UnsupportedAlgorithm(alg.name().to_string()));
+        }
+        Ok(Self {
+            alg,
+            public: PublicKey::from(*public),
+            sender: None,
+            apu: None,
+            apv: None,
+        })
+    }
+
+    /// Create an encrypter authenticating the sender for the algorithm, `ECDH-1PU+XC20PKW` or
+    /// `ECDH-1PU+A256KW`, the 32 byte secret key of the sender, the key identifier of the sender
+    /// and the 32 byte public key of the recipient.
+    ///
+    /// The key identifier is set as the `skid` header parameter.
+    pub fn authenticated(
+        alg: &str,
+        sender: &[u8; 32],
+        skid: &str,
+        public: &[u8; 32],
+    ) -> Result<Self, Error> {
+        let alg = Algorithm::from_name(alg)?;
+        if !alg.authenticated {
+            return Err(Error::UnsupportedAlgorithm(alg.name().to_string()));
+        }
+        Ok(Self {
+            alg,
+            public: PublicKey::from(*public),
+            sender: Some((skid.to_string(), StaticSecret::from(*sender))),
+            apu: None,
+            apv: None,
+        })
+    }
+
+    /// Set the agreement party information bound into the key derivation, the `apu` and `apv`
+    /// header parameters.
+    pub fn with_party_info(mut self, apu: &[u8], apv: &[u8]) -> Self {
+        self.apu = Some(apu.to_vec());
+        self.apv = Some(apv.to_vec());
+        self
+    }
+}
+
+impl KeyEncrypter for X25519Encrypter {
+    fn algorithm(&self) -> &str {
+        self.alg.name()
+    }
+
+    fn encrypt_key(
+        &self,
+        cek: &[u8],
+        header: &mut BTreeMap<String, Ipld>,
+        tag: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        let secret: [u8; 32] = random_bytes(32)?
+            .try_into()
+            .expect("32 random bytes were requested");
+        let ephemeral = StaticSecret::from(secret);
+        let mut z = agree(&ephemeral, &self.public)?.to_vec();
+        header.insert("epk".to_string(), epk(&PublicKey::from(&ephemeral)));
+        if let Some((skid, sender)) = &self.sender {
+            z.extend_from_slice(&agree(sender, &self.public)?);
+            header.insert("skid".to_string(), Ipld::String(skid.clone()));
+        }
+        for (name, value) in [("apu", &self.apu), ("apv", &self.apv)] {
+            if let Some(value) = value {
+                header.insert(name.to_string(), Ipld::String(base64_url::encode(value)));
+            }
+        }
+        let kek = concat_kdf(
+            &z,
+            self.alg.name(),
+            self.apu.as_deref().unwrap_or_default(),
+            self.apv.as_deref().unwrap_or_default(),
+            self.alg.authenticated.then_some(tag),
+        );
+        self.alg.wrap.wrap(&kek, cek, header)
+    }
+}
+
+/// Decrypts content encryption keys of recipients using key agreement with an X25519 secret key.
+///
+/// ECDH-1PU recipients are only decrypted when the public key of the sender named by their
+/// `skid` header parameter has been added with [`X25519Decrypter::with_sender`].
+pub struct X25519Decrypter {
+    secret: StaticSecret,
+    senders: BTreeMap<String, PublicKey>,
+}
+
+impl X25519Decrypter {
+    /// Create a decrypter from the 32 byte secret key.
+    pub fn from_bytes(secret: &[u8; 32]) -> Self {
+        Self {
+            secret: StaticSecret::from(*secret),
+            senders: BTreeMap::new(),
+        }
+    }
+
+    /// The public key of the recipient.
+    pub fn public_key(&self) -> [u8; 32] {
+        PublicKey::from(&self.secret).to_bytes()
+    }
+
+    /// Accept ECDH-1PU recipients from the sender with the key identifier and 32 byte public key.
+    pub fn with_sender(mut self, skid: &str, public: &[u8; 32]) -> Self {
+        self.senders
+            .insert(skid.to_string(), PublicKey::from(*public));
+        self
+    }
+}
+
+impl KeyDecrypter for X25519Decrypter {
+    fn decrypt_key(
+        &self,
+        header: &ProtectedHeader,
+        encrypted_key: &[u8],
+        tag: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        let alg = Algorithm::from_name(header.alg.as_deref().ok_or(Error::MissingAlgorithm)?)?;
+        let epk = header
+            .additional
+            .get("epk")
+            .ok_or(Error::MissingHeader("epk"))?;
+        let mut z = agree(&self.secret, &parse_epk(epk)?)?.to_vec();
+        if alg.authenticated {
+            let skid = match header.additional.get("skid") {
+                Some(Ipld::String(skid)) => skid,
+                Some(_) => return Err(Error::InvalidHeader("skid")),
+                None => return Err(Error::MissingHeader("skid")),
+            };
+            let sender = self
+                .senders
+                .get(skid)
+                .ok_or_else(|| Error::UnknownSender(skid.clone()))?;
+            z.extend_from_slice(&agree(&self.secret, sender)?);
+        }
+        let kek = concat_kdf(
+            &z,
+            alg.name(),
+            &header_bytes(header, "apu")?.unwrap_or_default(),
+            &header_bytes(header, "apv")?.unwrap_or_default(),
+            alg.authenticated.then_some(tag),
+        );
+        alg.wrap.unwrap(&kek, encrypted_key, header)
+    }
+}
+
+/// Compute the shared secret, rejecting low order public keys.
+fn agree(secret: &StaticSecret, public: &PublicKey) -> Result<[u8; 32], Error> {
+    let shared = secret.diffie_hellman(public);
+    if !shared.was_contributory() {
+        return Err(Error::InvalidKey("X25519"));
+    }
+    Ok(shared.to_bytes())
+}
+
+/// Derive the 256 bit key encryption key using the Concat KDF with SHA-256.
+///
+/// Every supported key wrapping algorithm uses a 256 bit key so a single round of the hash is
+/// needed. For ECDH-1PU the authentication tag is appended to the supplementary public info.
+///
+/// See https://www.rfc-editor.org/rfc/rfc7518#section-4.6.2 and
+/// https://datatracker.ietf.org/doc/html/draft-madden-jose-ecdh-1pu-04#section-2.3
+fn concat_kdf(z: &[u8], alg: &str, apu: &[u8], apv: &[u8], tag: Option<&[u8]>) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(1u32.to_be_bytes());
+    hasher.update(z);
+    for value in [alg.as_bytes(), apu, apv] {
+        hasher.update((value.len() as u32).to_be_bytes());
+        hasher.update(value);
+    }
+    hasher.update(256u32.to_be_bytes());
+    if let Some(tag) = tag {
+        hasher.update((tag.len() as u32).to_be_bytes());
+        hasher.update(tag);
+    }
+    hasher.finalize().into()
+}
+
+/// The `epk` header parameter for an X25519 public key.
+fn epk(public: &PublicKey) -> Ipld {
+    Ipld::from(&Jwk::Okp {
+        crv: "X25519".to_string(),
+        x: public.as_bytes().to_vec(),
+    })
+}
+
+fn parse_epk(epk: &Ipld) -> Result<PublicKey, Error> {
+    match Jwk::try_from(epk).map_err(|_| Error::InvalidHeader("epk"))? {
+        Jwk::Okp { crv, x } if crv == "X25519" => {
+            let x: [u8; 32] = x.try_into().map_err(|_| Error::InvalidKey("X25519"))?;
+            Ok(PublicKey::from(x))
+        }
+        _ => Err(Error::InvalidKey("X25519")),
+    }
+}
+
+/// Decode a base64 url encoded header parameter.
+fn header_bytes(header: &ProtectedHeader, name: &'static str) -> Result<Option<Vec<u8>>, Error> {
+    match header.additional.get(name) {
+        None => Ok(None),
+        Some(Ipld::String(value)) => Ok(Some(base64_url::decode(value)?)),
+        Some(_) => Err(Error::InvalidHeader(name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Bytes, JsonWebEncryption, JweBuilder};
+
+    const PLAINTEXT: &[u8] = b"The true sign of intelligence is not knowledge but imagination.";
+
+    // Recipient secret key 0x01..=0x20, the JWE values were produced with an independent
+    // implementation.
+    const PUBLIC: &str = "B6N8vBQgk8i3VdwbEOhstCY3StFqqFPtC9_AsrhtHHw";
+    const XC20PKW_JWE: &str = r#"{"protected":"eyJlbmMiOiJYQzIwUCJ9","iv":"AAECAwQFBgcICQoLDA0ODxAREhMUFRYX","ciphertext":"jbykSdwVZOE9p9TuXJht3ukyc-5Gj_HUTb568YTj63k9A5rdy57mXPIzvJb8a9oVgunDyxnG147A73JnmoJ9","tag":"QWf7GF40gmVkRB_ZfuK3ig","recipients":[{"encrypted_key":"zn7hDMag6fxSwQK5w3LED5SS1IBtHmCU4nF2jHT1KbU","header":{"alg":"ECDH-ES+XC20PKW","epk":{"kty":"OKP","crv":"X25519","x":"WGmv9FBUlzLLqu1eXfmzCm2jHLDldCutWtShp2jxpns"},"apu":"QWxpY2U","apv":"Qm9i","iv":"MjM0NTY3ODk6Ozw9Pj9AQUJDREVGR0hJ","tag":"uogNUqp5wcZUcJX-Ds4eQg"}}]}"#;
+    const A256KW_JWE: &str = r#"{"protected":"eyJlbmMiOiJYQzIwUCJ9","iv":"AAECAwQFBgcICQoLDA0ODxAREhMUFRYX","ciphertext":"jbykSdwVZOE9p9TuXJht3ukyc-5Gj_HUTb568YTj63k9A5rdy57mXPIzvJb8a9oVgunDyxnG147A73JnmoJ9","tag":"QWf7GF40gmVkRB_ZfuK3ig","recipients":[{"encrypted_key":"WRfsNtTl-vkSGQr97HKyeO2NvCtpkq86xPQYUPd0eVRYVfuIwNz7ig","header":{"alg":"ECDH-ES+A256KW","epk":{"kty":"OKP","crv":"X25519","x":"WGmv9FBUlzLLqu1eXfmzCm2jHLDldCutWtShp2jxpns"}}}]}"#;
+
+    // Sender secret key 0x41..=0x60.
+    const SENDER_PUBLIC: &str = "ZLEBsdC-WocEvQePmJUAH8A-jp-VIvGI3RKNmEbUhGY";
+    const XC20PKW_1PU_JWE: &str = r#"{"protected":"eyJlbmMiOiJYQzIwUCJ9","iv":"AAECAwQFBgcICQoLDA0ODxAREhMUFRYX","ciphertext":"jbykSdwVZOE9p9TuXJht3ukyc-5Gj_HUTb568YTj63k9A5rdy57mXPIzvJb8a9oVgunDyxnG147A73JnmoJ9","tag":"QWf7GF40gmVkRB_ZfuK3ig","recipients":[{"encrypted_key":"8iTxkTxD8ceufBWg89Jt0d2UNBctELENDWL58b5Nu2E","header":{"alg":"ECDH-1PU+XC20PKW","skid":"alice","epk":{"kty":"OKP","crv":"X25519","x":"WGmv9FBUlzLLqu1eXfmzCm2jHLDldCutWtShp2jxpns"},"apu":"QWxpY2U","apv":"Qm9i","iv":"MjM0NTY3ODk6Ozw9Pj9AQUJDREVGR0hJ","tag":"LXny2igJBWL4VpCXi-sIdA"}}]}"#;
+    const A256KW_1PU_JWE: &str = r#"{"protected":"eyJlbmMiOiJYQzIwUCJ9","iv":"AAECAwQFBgcICQoLDA0ODxAREhMUFRYX","ciphertext":"jbykSdwVZOE9p9TuXJht3ukyc-5Gj_HUTb568YTj63k9A5rdy57mXPIzvJb8a9oVgunDyxnG147A73JnmoJ9","tag":"QWf7GF40gmVkRB_ZfuK3ig","recipients":[{"encrypted_key":"wqCoHLbwJ02bfgNLoxYS9oLJdq2ZWQw0c6LlK_Fx-vM77A323YZOKQ","header":{"alg":"ECDH-1PU+A256KW","skid":"alice","epk":{"kty":"OKP","crv":"X25519","x":"WGmv9FBUlzLLqu1eXfmzCm2jHLDldCutWtShp2jxpns"}}}]}"#;
+
+    fn sender_public() -> [u8; 32] {
+        base64_url::decode(SENDER_PUBLIC)
+            .unwrap()
+            .try_into()
+            .unwrap()
+    }
+
+    fn decrypter() -> X25519Decrypter {
+        X25519Decrypter::from_bytes(&core::array::from_fn(|i| i as u8 + 1))
+    }
+
+    #[test]
+    fn decrypt_vectors() {
+        let decrypter = decrypter();
+        assert_eq!(base64_url::encode(&decrypter.public_key()), PUBLIC);
+        for json in [XC20PKW_JWE, A256KW_JWE] {
+            let jwe = JsonWebEncryption::from_json(json).unwrap();
+            assert_eq!(jwe.decrypt(&decrypter).unwrap(), PLAINTEXT);
+        }
+        // The party information is bound into the key derivation.
+        let jwe = JsonWebEncryption::from_json(&XC20PKW_JWE.replace("Qm9i", "Qm9j")).unwrap();
+        assert!(matches!(jwe.decrypt(&decrypter), Err(Error::Decryption)));
+        // Another recipient cannot decrypt the key.
+        let jwe = JsonWebEncryption::from_json(A256KW_JWE).unwrap();
+        assert!(matches!(
+            jwe.decrypt(&X25519Decrypter::from_bytes(&[9; 32])),
+            Err(Error::KeyUnwrap)
+        ));
+    }
+
+    #[test]
+    fn roundtrip() {
+        let alice = decrypter();
+        let bob = X25519Decrypter::from_bytes(&[9; 32]);
+        let to_alice = X25519Encrypter::new("ECDH-ES+XC20PKW", &alice.public_key())
+            .unwrap()
+            .with_party_info(b"sender", b"alice");
+        let to_bob = X25519Encrypter::new("ECDH-ES+A256KW", &bob.public_key()).unwrap();
+        let jwe = JweBuilder::new(ContentEncryption::A256Gcm)
+            .recipient(&to_alice)
+            .recipient(&to_bob)
+            .build(PLAINTEXT)
+            .unwrap();
+        assert_eq!(jwe.recipients.len(), 2);
+        let header = jwe.header(Some(&jwe.recipients[0])).unwrap();
+        assert_eq!(header.alg.as_deref(), Some("ECDH-ES+XC20PKW"));
+        assert_eq!(header.additional.get("apv"), Some(&Ipld::from("YWxpY2U")));
+        assert_eq!(jwe.decrypt(&alice).unwrap(), PLAINTEXT);
+        assert_eq!(jwe.decrypt(&bob).unwrap(), PLAINTEXT);
+    }
+
+    #[test]
+    fn reject_invalid_epk() {
+        let json = XC20PKW_JWE.replace("\"crv\":\"X25519\"", "\"crv\":\"X448\"");
+        let jwe = JsonWebEncryption::from_json(&json).unwrap();
+        assert!(matches!(
+            jwe.decrypt(&decrypter()),
+            Err(Error::InvalidKey("X25519"))
+        ));
+        // The all zero public key is of low order.
+        let json = A256KW_JWE.replace(
+            "WGmv9FBUlzLLqu1eXfmzCm2jHLDldCutWtShp2jxpns",
+            "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA",
+        );
+        let jwe = JsonWebEncryption::from_json(&json).unwrap();
+        assert!(matches!(
+            jwe.decrypt(&decrypter()),
+            Err(Error::InvalidKey("X25519"))
+        ));
+        assert!(matches!(
+            X25519Encrypter::new("ECDH-ES", &[9; 32]),
+            Err(Error::UnsupportedAlgorithm(_))
+        ));
+        // ECDH-1PU needs the sender key of the authenticated constructor.
+        assert!(matches!(
+            X25519Encrypter::new("ECDH-1PU+A256KW", &[9; 32]),
+            Err(Error::UnsupportedAlgorithm(ref alg)) if alg == "ECDH-1PU+A256KW"
+        ));
+    }
+
+    #[test]
+    fn decrypt_1pu_vectors() {
+        let decrypter = decrypter().with_sender("alice", &sender_public());
+        for json in [XC20PKW_1PU_JWE, A256KW_1PU_JWE] {
+            let jwe = JsonWebEncryption::from_json(json).unwrap();
+            assert_eq!(jwe.decrypt(&decrypter).unwrap(), PLAINTEXT);
+            // The sender must be known.
+            assert!(matches!(
+                jwe.decrypt(&super::tests::decrypter()),
+                Err(Error::UnknownSender(ref skid)) if skid == "alice"
+            ));
+        }
+        // The sender must be identified.
+        let jwe = JsonWebEncryption::from_json(&A256KW_1PU_JWE.replace(r#""skid":"alice","#, ""))
+            .unwrap();
+        assert!(matches!(
+            jwe.decrypt(&decrypter),
+            Err(Error::MissingHeader("skid"))
+        ));
+        // The content tag is bound into the key derivation, so the content cannot be replaced
+        // even with a valid tag for another content.
+        let mut jwe = JsonWebEncryption::from_json(A256KW_1PU_JWE).unwrap();
+        jwe.tag = Bytes::from_base64("QWf7GF40gmVkRB_ZfuK3iw").unwrap();
+        assert!(matches!(jwe.decrypt(&decrypter), Err(Error::KeyUnwrap)));
+    }
+
+    #[test]
+    fn roundtrip_1pu() {
+        let alice = decrypter().with_sender("did:example:sender#key-1", &sender_public());
+        let sender: [u8; 32] = core::array::from_fn(|i| i as u8 + 0x41);
+        let to_alice = X25519Encrypter::authenticated(
+            "ECDH-1PU+XC20PKW",
+            &sender,
+            "did:example:sender#key-1",
+            &alice.public_key(),
+        )
+        .unwrap();
+        let jwe = JweBuilder::new(ContentEncryption::XC20P)
+            .recipient(&to_alice)
+            .build(PLAINTEXT)
+            .unwrap();
+        assert_eq!(
+            jwe.recipients[0].header.get("skid"),
+            Some(&Ipld::from("did:example:sender#key-1"))
+        );
+        assert_eq!(jwe.decrypt(&alice).unwrap(), PLAINTEXT);
+
+        // Another sender cannot impersonate the sender.
+        let impostor = decrypter().with_sender("did:example:sender#key-1", &[9; 32]);
+        assert!(matches!(jwe.decrypt(&impostor), Err(Error::Decryption)));
+
+        assert!(matches!(
+            X25519Encrypter::authenticated("ECDH-ES+A256KW", &sender, "s", &[9; 32]),
+            Err(Error::UnsupportedAlgorithm(ref alg)) if alg == "ECDH-ES+A256KW"
+        ));
+    }
+}
@@ -0,0 +1,293 @@
+//! ES256 and ES384 JSON Web Signatures using NIST P-256 and P-384 keys.
+//!
+//! Signatures are the fixed length concatenation of R and S, 64 bytes for ES256 and 96 bytes for
+//! ES384.
+//!
+//! See https://www.rfc-editor.org/rfc/rfc7518#section-3.4
+use crate::{error::Error, jws::is_der_signature, Signer, Verifier};
+
+#[cfg(feature = "es256")]
+const ES256: &str = "ES256";
+#[cfg(feature = "es384")]
+const ES384: &str = "ES384";
+
+/// Signs JWS values with a P-256 or P-384 private key.
+pub enum EcdsaSigner {
+    /// ES256 signer using a P-256 key.
+    #[cfg(feature = "es256")]
+    Es256(p256::ecdsa::SigningKey),
+    /// ES384 signer using a P-384 key.
+    #[cfg(feature = "es384")]
+    Es384(p384::ecdsa::SigningKey),
+}
+
+impl EcdsaSigner {
+    /// Create a signer for the algorithm from the private key scalar.
+    ///
+    /// The key must be 32 bytes for ES256 and 48 bytes for ES384. An algorithm whose feature is
+    /// not enabled is an `UnsupportedAlgorithm`.
+    pub fn from_bytes(alg: &str, secret: &[u8]) -> Result<Self, Error> {
+        let curve = Curve::from_secret_len(secret.len())?;
+        match (alg, curve) {
+            #[cfg(feature = "es256")]
+            (ES256, Curve::P256) => Ok(Self::Es256(
+                p256::ecdsa::SigningKey::from_slice(secret)
+                    .map_err(|_| Error::InvalidKey("P-256"))?,
+            )),
+            #[cfg(feature = "es384")]
+            (ES384, Curve::P384) => Ok(Self::Es384(
+                p384::ecdsa::SigningKey::from_slice(secret)
+                    .map_err(|_| Error::InvalidKey("P-384"))?,
+            )),
+            #[cfg(feature = "es256")]
+            (ES256, curve) => Err(curve.mismatch(ES256)),
+            #[cfg(feature = "es384")]
+            (ES384, curve) => Err(curve.mismatch(ES384)),
+            (alg, _) => Err(Error::UnsupportedAlgorithm(alg.to_string())),
+        }
+    }
+
+    /// The verifier for signatures produced by this signer.
+    pub fn verifier(&self) -> EcdsaVerifier {
+        match self {
+            #[cfg(feature = "es256")]
+            Self::Es256(key) => EcdsaVerifier::Es256(*key.verifying_key()),
+            #[cfg(feature = "es384")]
+            Self::Es384(key) => EcdsaVerifier::Es384(*key.verifying_key()),
+        }
+    }
+}
+
+impl Signer for EcdsaSigner {
+    fn algorithm(&self) -> &str {
+        match self {
+            #[cfg(feature = "es256")]
+            Self::Es256(_) => ES256,
+            #[cfg(feature = "es384")]
+            Self::Es384(_) => ES384,
+        }
+    }
+
+    fn sign(&self, signing_input: &[u8]) -> Result<Vec<u8>, Error> {
+        match self {
+            #[cfg(feature = "es256")]
+            Self::Es256(key) => {
+                use p256::ecdsa::signature::Signer as _;
+                let signature: p256::ecdsa::Signature = key.sign(signing_input);
+                Ok(signature.to_bytes().to_vec())
+            }
+            #[cfg(feature = "es384")]
+            Self::Es384(key) => {
+                use p384::ecdsa::signature::Signer as _;
+                let signature: p384::ecdsa::Signature = key.sign(signing_input);
+                Ok(signature.to_bytes().to_vec())
+            }
+        }
+    }
+}
+
+/// Verifies JWS values with a P-256 or P-384 public key.
+pub enum EcdsaVerifier {
+    /// ES256 verifier using a P-256 key.
+    #[cfg(feature = "es256")]
+    Es256(p256::ecdsa::VerifyingKey),
+    /// ES384 verifier using a P-384 key.
+    #[cfg(feature = "es384")]
+    Es384(p384::ecdsa::VerifyingKey),
+}
+
+impl EcdsaVerifier {
+    /// Create a verifier for the algorithm from a SEC1 encoded public key, either compressed or
+    /// uncompressed.
+    ///
+    /// An algorithm whose feature is not enabled is an `UnsupportedAlgorithm`.
+    pub fn from_sec1_bytes(alg: &str, public: &[u8]) -> Result<Self, Error> {
+        let curve = Curve::from_sec1_len(public.len())?;
+        match (alg, curve) {
+            #[cfg(feature = "es256")]
+            (ES256, Curve::P256) => Ok(Self::Es256(
+                p256::ecdsa::VerifyingKey::from_sec1_bytes(public)
+                    .map_err(|_| Error::InvalidKey("P-256"))?,
+            )),
+            #[cfg(feature = "es384")]
+            (ES384, Curve::P384) => Ok(Self::Es384(
+                p384::ecdsa::VerifyingKey::from_sec1_bytes(public)
+                    .map_err(|_| Error::InvalidKey("P-384"))?,
+            )),
+            #[cfg(feature = "es256")]
+            (ES256, curve) => Err(curve.mismatch(ES256)),
+            #[cfg(feature = "es384")]
+            (ES384, curve) => Err(curve.mismatch(ES384)),
+            (alg, _) => Err(Error::UnsupportedAlgorithm(alg.to_string())),
+        }
+    }
+}
+
+impl Verifier for EcdsaVerifier {
+    fn algorithm(&self) -> &str {
+        match self {
+            #[cfg(feature = "es256")]
+            Self::Es256(_) => ES256,
+            #[cfg(feature = "es384")]
+            Self::Es384(_) => ES384,
+        }
+    }
+
+    fn verify(&self, signing_input: &[u8], signature: &[u8]) -> Result<(), Error> {
+        let raw_len = match self {
+            #[cfg(feature = "es256")]
+            Self::Es256(_) => 64,
+            #[cfg(feature = "es384")]
+            Self::Es384(_) => 96,
+        };
+        if is_der_signature(signature, raw_len) {
+            return Err(Error::DerSignature);
+        }
+        match self {
+            #[cfg(feature = "es256")]
+            Self::Es256(key) => {
+                use p256::ecdsa::signature::Verifier as _;
+                let signature = p256::ecdsa::Signature::from_slice(signature)
+                    .map_err(|_| Error::InvalidSignature)?;
+                key.verify(signing_input, &signature)
+                    .map_err(|_| Error::InvalidSignature)
+            }
+            #[cfg(feature = "es384")]
+            Self::Es384(key) => {
+                use p384::ecdsa::signature::Verifier as _;
+                let signature = p384::ecdsa::Signature::from_slice(signature)
+                    .map_err(|_| Error::InvalidSignature)?;
+                key.verify(signing_input, &signature)
+                    .map_err(|_| Error::InvalidSignature)
+            }
+        }
+    }
+}
+
+/// The curve of a key, determined from its encoded length.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Curve {
+    P256,
+    P384,
+}
+
+impl Curve {
+    fn from_secret_len(len: usize) -> Result<Self, Error> {
+        match len {
+            32 => Ok(Self::P256),
+            48 => Ok(Self::P384),
+            _ => Err(Error::InvalidKey("ECDSA")),
+        }
+    }
+
+    fn from_sec1_len(len: usize) -> Result<Self, Error> {
+        match len {
+            33 | 65 => Ok(Self::P256),
+            49 | 97 => Ok(Self::P384),
+            _ => Err(Error::InvalidKey("ECDSA")),
+        }
+    }
+
+    fn mismatch(self, alg: &str) -> Error {
+        Error::CurveMismatch {
+            alg: alg.to_string(),
+            curve: match self {
+                Self::P256 => "P-256",
+                Self::P384 => "P-384",
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "es256")]
+    #[test]
+    fn verify_rfc7515_es256() {
+        // Example from https://www.rfc-editor.org/rfc/rfc7515#appendix-A.3
+        let x = base64_url::decode("f83OJ3D2xF1Bg8vub9tLe1gHMzV76e8Tus9uPHvRVEU").unwrap();
+        let y = base64_url::decode("x_FEzRu9m36HLN_tue659LNpXW6pCyStikYjKIWI5a0").unwrap();
+        let verifier =
+            EcdsaVerifier::from_sec1_bytes(ES256, &[&[0x04], &x[..], &y[..]].concat()).unwrap();
+        let signing_input = "eyJhbGciOiJFUzI1NiJ9.eyJpc3MiOiJqb2UiLA0KICJleHAiOjEzMDA4MTkzODAsDQogImh0dHA6Ly9leGFtcGxlLmNvbS9pc19yb290Ijp0cnVlfQ";
+        let signature = base64_url::decode("DtEhU3ljbEg8L38VWAfUAqOyKAM6-Xx-F4GawxaepmXFCgfTjDxw5djxLa8ISlSApmWQxfKTUJqPP3-Kg6NU1Q").unwrap();
+        assert!(verifier
+            .verify(signing_input.as_bytes(), &signature)
+            .is_ok());
+        assert!(matches!(
+            verifier.verify(b"eyJhbGciOiJFUzI1NiJ9.e30", &signature),
+            Err(Error::InvalidSignature)
+        ));
+    }
+
+    #[cfg(feature = "es256")]
+    #[test]
+    fn sign_verify_es256() {
+        let signer = EcdsaSigner::from_bytes(ES256, &[7; 32]).unwrap();
+        let signing_input = b"eyJhbGciOiJFUzI1NiJ9.e30";
+        let signature = signer.sign(signing_input).unwrap();
+        assert_eq!(signature.len(), 64);
+        assert!(signer.verifier().verify(signing_input, &signature).is_ok());
+    }
+
+    #[cfg(feature = "es384")]
+    #[test]
+    fn sign_verify_es384() {
+        let signer = EcdsaSigner::from_bytes(ES384, &[7; 48]).unwrap();
+        let signing_input = b"eyJhbGciOiJFUzM4NCJ9.e30";
+        let signature = signer.sign(signing_input).unwrap();
+        assert_eq!(signature.len(), 96);
+        assert!(signer.verifier().verify(signing_input, &signature).is_ok());
+        assert!(matches!(
+            signer.verifier().verify(signing_input, &signature[..64]),
+            Err(Error::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn reject_curve_mismatch() {
+        #[cfg(feature = "es384")]
+        assert!(matches!(
+            EcdsaSigner::from_bytes(ES384, &[7; 32]),
+            Err(Error::CurveMismatch { curve: "P-256", .. })
+        ));
+        #[cfg(feature = "es256")]
+        assert!(matches!(
+            EcdsaVerifier::from_sec1_bytes(ES256, &[2; 49]),
+            Err(Error::CurveMismatch { curve: "P-384", .. })
+        ));
+    }
+
+    #[test]
+    fn reject_unsupported_algorithm() {
+        assert!(matches!(
+            EcdsaSigner::from_bytes("ES512", &[7; 32]),
+            Err(Error::UnsupportedAlgorithm(alg)) if alg == "ES512"
+        ));
+        assert!(matches!(
+            EcdsaVerifier::from_sec1_bytes("ES256K", &[2; 33]),
+            Err(Error::UnsupportedAlgorithm(alg)) if alg == "ES256K"
+        ));
+        #[cfg(not(feature = "es384"))]
+        assert!(matches!(
+            EcdsaSigner::from_bytes("ES384", &[7; 48]),
+            Err(Error::UnsupportedAlgorithm(_))
+        ));
+    }
+
+    #[cfg(feature = "es256")]
+    #[test]
+    fn reject_der() {
+        let signer = EcdsaSigner::from_bytes(ES256, &[7; 32]).unwrap();
+        let mut der = vec![0x30, 0x44, 0x02, 0x20];
+        der.extend_from_slice(&[0x11; 32]);
+        der.extend_from_slice(&[0x02, 0x20]);
+        der.extend_from_slice(&[0x22; 32]);
+        assert!(matches!(
+            signer.verifier().verify(b"eyJhbGciOiJFUzI1NiJ9.e30", &der),
+            Err(Error::DerSignature)
+        ));
+    }
+}
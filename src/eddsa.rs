@@ -0,0 +1,100 @@
+//! EdDSA JSON Web Signatures using Ed25519 keys.
+//!
+//! See https://www.rfc-editor.org/rfc/rfc8037#section-3.1
+use ed25519_dalek::{Signer as _, SigningKey, VerifyingKey};
+
+use crate::{error::Error, Signer, Verifier};
+
+const ALGORITHM: &str = "EdDSA";
+
+/// Signs JWS values with an Ed25519 private key.
+pub struct Ed25519Signer(SigningKey);
+
+impl Ed25519Signer {
+    /// Create a signer from the 32 byte private key seed.
+    pub fn from_bytes(secret: &[u8; 32]) -> Self {
+        Self(SigningKey::from_bytes(secret))
+    }
+
+    /// The verifier for signatures produced by this signer.
+    pub fn verifier(&self) -> Ed25519Verifier {
+        Ed25519Verifier(self.0.verifying_key())
+    }
+}
+
+impl Signer for Ed25519Signer {
+    fn algorithm(&self) -> &str {
+        ALGORITHM
+    }
+
+    fn sign(&self, signing_input: &[u8]) -> Result<Vec<u8>, Error> {
+        Ok(self.0.sign(signing_input).to_vec())
+    }
+}
+
+/// Verifies JWS values with an Ed25519 public key.
+pub struct Ed25519Verifier(VerifyingKey);
+
+impl Ed25519Verifier {
+    /// Create a verifier from the 32 byte public key.
+    pub fn from_bytes(public: &[u8; 32]) -> Result<Self, Error> {
+        Ok(Self(
+            VerifyingKey::from_bytes(public).map_err(|_| Error::InvalidKey("ed25519"))?,
+        ))
+    }
+}
+
+impl Verifier for Ed25519Verifier {
+    fn algorithm(&self) -> &str {
+        ALGORITHM
+    }
+
+    fn verify(&self, signing_input: &[u8], signature: &[u8]) -> Result<(), Error> {
+        let signature =
+            ed25519_dalek::Signature::from_slice(signature).map_err(|_| Error::InvalidSignature)?;
+        self.0
+            .verify_strict(signing_input, &signature)
+            .map_err(|_| Error::InvalidSignature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test vector from https://www.rfc-editor.org/rfc/rfc8037#appendix-A.4
+    const SECRET: &str = "nWGxne_9WmC6hEr0kuwsxERJxWl7MmkZcDusAxyuf2A";
+    const PUBLIC: &str = "11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo";
+    const SIGNING_INPUT: &str = "eyJhbGciOiJFZERTQSJ9.RXhhbXBsZSBvZiBFZDI1NTE5IHNpZ25pbmc";
+    const SIGNATURE: &str =
+        "hgyY0il_MGCjP0JzlnLWG1PPOt7-09PGcvMg3AIbQR6dWbhijcNR4ki4iylGjg5BhVsPt9g7sVvpAr_MuM0KAg";
+
+    fn key(value: &str) -> [u8; 32] {
+        base64_url::decode(value).unwrap().try_into().unwrap()
+    }
+
+    #[test]
+    fn sign_rfc8037() {
+        let signer = Ed25519Signer::from_bytes(&key(SECRET));
+        let signature = signer.sign(SIGNING_INPUT.as_bytes()).unwrap();
+        assert_eq!(base64_url::encode(&signature), SIGNATURE);
+    }
+
+    #[test]
+    fn verify_rfc8037() {
+        let verifier = Ed25519Verifier::from_bytes(&key(PUBLIC)).unwrap();
+        let mut signature = base64_url::decode(SIGNATURE).unwrap();
+        assert!(verifier
+            .verify(SIGNING_INPUT.as_bytes(), &signature)
+            .is_ok());
+        signature[0] ^= 1;
+        assert!(matches!(
+            verifier.verify(SIGNING_INPUT.as_bytes(), &signature),
+            Err(Error::InvalidSignature)
+        ));
+        assert!(matches!(
+            verifier.verify(SIGNING_INPUT.as_bytes(), &signature[1..]),
+            Err(Error::InvalidSignature)
+        ));
+    }
+}
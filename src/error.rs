@@ -2,25 +2,186 @@
 use ipld_core::cid;
 use thiserror::Error;
 
+/// Errors produced when encoding, decoding or converting JOSE values.
 #[derive(Error, Debug)]
 pub enum Error {
+    /// The data does not represent a JWE value.
     #[error("data not a JWE value")]
     NotJwe,
+    /// The data does not represent a JWS value.
     #[error("data not a JWS value")]
     NotJws,
+    /// The DAG-JOSE value has a field that is neither a JWS nor a JWE field.
+    #[error("unknown field: {0}")]
+    UnknownField(String),
+    /// The DAG-JOSE value has both JWS and JWE fields.
+    #[error("JWS field {jws} mixed with JWE field {jwe}")]
+    MixedFields {
+        /// A JWS field of the value.
+        jws: String,
+        /// A JWE field of the value.
+        jwe: String,
+    },
+    /// The block is not the canonical DAG-CBOR encoding of its value.
+    #[error(
+        "non-canonical encoding at byte {offset}{}",
+        field.as_ref().map(|field| format!(" in field {field}")).unwrap_or_default()
+    )]
+    NonCanonical {
+        /// The offset of the first byte differing from the canonical encoding.
+        offset: usize,
+        /// The path of map keys and list indexes to the field containing the byte, if any.
+        field: Option<String>,
+    },
+    /// The DAG-JOSE JWS value has an empty `signatures` list.
+    #[error("empty signatures field")]
+    EmptySignatures,
+    /// The payload is not a valid CID.
     #[error("invalid CID data in payload")]
     InvalidCid(#[from] cid::Error),
+    /// A field is not valid base64 url encoded data.
     #[error("invalid base64 url data")]
     InvalidBase64Url(#[from] base64_url::base64::DecodeError),
+    /// The compact serialization is malformed.
+    #[error("invalid compact serialization: {0}")]
+    InvalidCompact(&'static str),
+    /// The value cannot be represented using the compact serialization.
+    #[error("value not representable in compact serialization: {0}")]
+    NotCompact(&'static str),
+    /// The JSON serialization is malformed.
+    #[error("invalid JSON serialization: {0}")]
+    InvalidJsonSerialization(&'static str),
+    /// The value cannot be represented using the flattened JSON serialization.
+    #[error("value not representable in flattened JSON serialization: {0}")]
+    NotFlattened(&'static str),
+    /// Failed to encode or decode JSON data.
+    #[error("invalid json")]
+    Json(#[from] serde_json::Error),
+    /// The protected header has no `alg` parameter.
+    #[error("missing alg header parameter")]
+    MissingAlgorithm,
+    /// The JWE header has no `enc` parameter.
+    #[error("missing enc header parameter")]
+    MissingContentEncryption,
+    /// A header parameter required by the algorithm is missing.
+    #[error("missing {0} header parameter")]
+    MissingHeader(&'static str),
+    /// A header parameter has an invalid value.
+    #[error("invalid {0} header parameter")]
+    InvalidHeader(&'static str),
+    /// The sender of an authenticated JWE recipient is not known.
+    #[error("unknown sender key: {0}")]
+    UnknownSender(String),
+    /// A header parameter occurs in more than one of the headers of a JWE recipient.
+    #[error("duplicate header parameter: {0}")]
+    DuplicateHeader(String),
+    /// No implementation is available for the algorithm.
+    #[error("unsupported algorithm: {0}")]
+    UnsupportedAlgorithm(String),
+    /// The protected header lists a critical extension that is not understood.
+    #[error("unsupported critical header parameter: {0}")]
+    UnsupportedCritical(String),
+    /// The key material is invalid for the algorithm.
+    #[error("invalid {0} key")]
+    InvalidKey(&'static str),
+    /// The key resolver has no key for the key id.
+    #[error("unknown key: {0}")]
+    UnknownKey(String),
+    /// The key type cannot be used for the operation.
+    #[error("{key} keys cannot be used for {usage}")]
+    KeyUsage {
+        /// The key type.
+        key: &'static str,
+        /// The attempted use of the key.
+        usage: &'static str,
+    },
+    /// The key curve cannot be used with the algorithm.
+    #[error("algorithm {alg} cannot be used with a {curve} key")]
+    CurveMismatch {
+        /// The requested algorithm.
+        alg: String,
+        /// The curve of the key.
+        curve: &'static str,
+    },
+    /// The RSA key modulus is shorter than the required minimum.
+    #[error("RSA key modulus of {bits} bits is shorter than the minimum of {min} bits")]
+    WeakRsaKey {
+        /// The size of the key modulus in bits.
+        bits: usize,
+        /// The minimum accepted modulus size in bits.
+        min: usize,
+    },
+    /// The JWS payload is not the binary form of its `link`.
+    #[error("payload CID {payload} does not match link {link}")]
+    LinkMismatch {
+        /// The CID in the `link` field.
+        link: String,
+        /// The CID decoded from the payload.
+        payload: String,
+    },
+    /// No signer was provided to produce a JWS.
+    #[error("at least one signer is required")]
+    MissingSigner,
+    /// The signature does not match the signing input.
+    #[error("invalid signature")]
+    InvalidSignature,
+    /// The JWE is malformed.
+    #[error("invalid JWE: {0}")]
+    InvalidJwe(&'static str),
+    /// No recipient was provided to produce a JWE.
+    #[error("at least one recipient is required")]
+    MissingRecipient,
+    /// The JWE content or key failed to encrypt.
+    #[error("encryption failed")]
+    Encryption,
+    /// The JWE content or key failed to decrypt or authenticate.
+    #[error("decryption failed")]
+    Decryption,
+    /// The integrity check of an AES wrapped key failed, the key encryption key is wrong or the
+    /// encrypted key was modified.
+    #[error("key unwrap integrity check failed")]
+    KeyUnwrap,
+    /// Failed to generate random bytes.
+    #[error("failed to generate random bytes")]
+    Random,
+    /// The ECDSA signature is DER encoded instead of the raw R || S encoding required by JWS.
+    #[error("DER encoded ECDSA signature, expected raw R || S")]
+    DerSignature,
+    /// The ECDSA signature S value is not in the lower half of the curve order.
+    #[error("ECDSA signature S value is not normalized")]
+    HighSSignature,
+    /// The JSON Web Key is malformed, naming the invalid member.
+    #[error("invalid JWK: {0}")]
+    InvalidJwk(&'static str),
+    /// The `did:key` identifier is malformed.
+    #[error("invalid did:key: {0}")]
+    InvalidDidKey(&'static str),
+    /// The multicodec of the public key is not supported.
+    #[error("unsupported multicodec: {0:#x}")]
+    UnsupportedMulticodec(u64),
+    /// The DAG-CBOR data is invalid.
     #[error("invalid cbor encoding")]
     Codec(#[from] serde_ipld_dagcbor::error::CodecError),
+    /// Failed to encode DAG-CBOR data.
     #[error("failed encoding")]
     CborEncode(#[from] serde_ipld_dagcbor::EncodeError<std::io::Error>),
+    /// Failed to decode DAG-CBOR data.
     #[error("failed decoding")]
     CborDecode(#[from] serde_ipld_dagcbor::DecodeError<std::io::Error>),
+    /// Failed to decode DAG-CBOR data borrowed from a slice.
+    #[error("failed decoding")]
+    CborDecodeSlice(#[from] serde_ipld_dagcbor::DecodeError<std::convert::Infallible>),
+    /// The DAG-CBOR data is malformed.
+    #[error("invalid DAG-CBOR data: {0}")]
+    InvalidCbor(&'static str),
+    /// Failed to read or write streamed data.
+    #[error("i/o error")]
+    Io(#[from] std::io::Error),
+    /// Failed to encode DAG-JSON data.
     #[cfg(feature = "dag-json")]
     #[error("failed encoding")]
     JsonEncode(#[from] serde_ipld_dagjson::EncodeError),
+    /// Failed to decode DAG-JSON data.
     #[cfg(feature = "dag-json")]
     #[error("failed decoding")]
     JsonDecode(#[from] serde_ipld_dagjson::DecodeError),
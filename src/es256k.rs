@@ -0,0 +1,153 @@
+//! ES256K JSON Web Signatures using secp256k1 keys.
+//!
+//! Signatures are the 64 byte concatenation of R and S with S normalized to the lower half of
+//! the curve order.
+//!
+//! See https://www.rfc-editor.org/rfc/rfc8812#section-3.2
+use k256::ecdsa::{signature::Signer as _, signature::Verifier as _, SigningKey, VerifyingKey};
+
+use crate::{error::Error, jws::is_der_signature, Signer, Verifier};
+
+const ALGORITHM: &str = "ES256K";
+
+/// Signs JWS values with a secp256k1 private key.
+pub struct Es256kSigner(SigningKey);
+
+impl Es256kSigner {
+    /// Create a signer from the 32 byte private key scalar.
+    pub fn from_bytes(secret: &[u8; 32]) -> Result<Self, Error> {
+        Ok(Self(
+            SigningKey::from_bytes(secret.into()).map_err(|_| Error::InvalidKey("secp256k1"))?,
+        ))
+    }
+
+    /// The verifier for signatures produced by this signer.
+    pub fn verifier(&self) -> Es256kVerifier {
+        Es256kVerifier(*self.0.verifying_key())
+    }
+}
+
+impl Signer for Es256kSigner {
+    fn algorithm(&self) -> &str {
+        ALGORITHM
+    }
+
+    fn sign(&self, signing_input: &[u8]) -> Result<Vec<u8>, Error> {
+        let signature: k256::ecdsa::Signature = self.0.sign(signing_input);
+        Ok(signature
+            .normalize_s()
+            .unwrap_or(signature)
+            .to_bytes()
+            .to_vec())
+    }
+}
+
+/// Verifies JWS values with a secp256k1 public key.
+pub struct Es256kVerifier(VerifyingKey);
+
+impl Es256kVerifier {
+    /// Create a verifier from a SEC1 encoded public key, either compressed or uncompressed.
+    pub fn from_sec1_bytes(public: &[u8]) -> Result<Self, Error> {
+        Ok(Self(
+            VerifyingKey::from_sec1_bytes(public).map_err(|_| Error::InvalidKey("secp256k1"))?,
+        ))
+    }
+}
+
+impl Verifier for Es256kVerifier {
+    fn algorithm(&self) -> &str {
+        ALGORITHM
+    }
+
+    fn verify(&self, signing_input: &[u8], signature: &[u8]) -> Result<(), Error> {
+        if is_der_signature(signature, 64) {
+            return Err(Error::DerSignature);
+        }
+        let signature =
+            k256::ecdsa::Signature::from_slice(signature).map_err(|_| Error::InvalidSignature)?;
+        if signature.normalize_s().is_some() {
+            return Err(Error::HighSSignature);
+        }
+        self.0
+            .verify(signing_input, &signature)
+            .map_err(|_| Error::InvalidSignature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Key used to produce the ES256K fixtures.
+    const SECRET: &str = "278a5de700e29faae8e40e366ec5012b5ec63d36ec77e8a2417154cc1d25383f";
+    const SIGNING_INPUT: &str =
+        "eyJhbGciOiJFUzI1NksifQ.AXASIN69ets85WVE0ipva5M5b2mAqAZ8LME08PeAG2MxCSuV";
+
+    fn signer() -> Es256kSigner {
+        Es256kSigner::from_bytes(&hex::decode(SECRET).unwrap().try_into().unwrap()).unwrap()
+    }
+
+    // Encode a raw R || S signature using ASN.1 DER.
+    fn der(signature: &[u8]) -> Vec<u8> {
+        let mut integers = Vec::new();
+        for part in signature.chunks(32) {
+            let part = &part[part.iter().take_while(|b| **b == 0).count()..];
+            let pad = part[0] & 0x80 != 0;
+            integers.push(0x02);
+            integers.push(part.len() as u8 + pad as u8);
+            if pad {
+                integers.push(0);
+            }
+            integers.extend_from_slice(part);
+        }
+        [vec![0x30, integers.len() as u8], integers].concat()
+    }
+
+    #[test]
+    fn sign_verify() {
+        let signer = signer();
+        let signature = signer.sign(SIGNING_INPUT.as_bytes()).unwrap();
+        assert_eq!(signature.len(), 64);
+        assert!(signer
+            .verifier()
+            .verify(SIGNING_INPUT.as_bytes(), &signature)
+            .is_ok());
+        assert!(matches!(
+            signer
+                .verifier()
+                .verify(b"eyJhbGciOiJFUzI1NksifQ.e30", &signature),
+            Err(Error::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn reject_der() {
+        let signer = signer();
+        let signature = signer.sign(SIGNING_INPUT.as_bytes()).unwrap();
+        assert!(matches!(
+            signer
+                .verifier()
+                .verify(SIGNING_INPUT.as_bytes(), &der(&signature)),
+            Err(Error::DerSignature)
+        ));
+        // A raw signature whose R happens to start like a DER sequence is still raw.
+        let mut raw = [0x11; 64];
+        raw[..3].copy_from_slice(&[0x30, 0x3e, 0x02]);
+        assert!(matches!(
+            signer.verifier().verify(SIGNING_INPUT.as_bytes(), &raw),
+            Err(Error::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn reject_high_s() {
+        // The jws-signature-2 fixture is the high S form of the jws-signature-1 fixture.
+        let signature = base64_url::decode("Q8PdTE5A5N3a0ktO2wNdUymumHlSxNF9Si38IvzsMaSZC63yQw-bJNpKf-UeJFPH7cDzY7jLg2G_viejp7NqXg").unwrap();
+        assert!(matches!(
+            signer()
+                .verifier()
+                .verify(SIGNING_INPUT.as_bytes(), &signature),
+            Err(Error::HighSSignature)
+        ));
+    }
+}
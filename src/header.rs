@@ -0,0 +1,227 @@
+//! Typed access to JOSE protected headers.
+use std::collections::BTreeMap;
+
+use ipld_core::ipld::Ipld;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{bytes::Bytes, error::Error, JsonWebEncryption, Recipient, Signature};
+
+/// A JOSE protected header with its registered parameters parsed.
+///
+/// Parameters without a dedicated field are kept in `additional`.
+///
+/// See https://www.rfc-editor.org/rfc/rfc7515#section-4.1 and
+/// https://www.rfc-editor.org/rfc/rfc7516#section-4.1
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProtectedHeader {
+    /// The signature or key management algorithm.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alg: Option<String>,
+    /// The content encryption algorithm.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enc: Option<String>,
+    /// The key identifier.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+    /// The media type of the complete JOSE value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub typ: Option<String>,
+    /// The media type of the secured content.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cty: Option<String>,
+    /// The extension parameters that must be understood and processed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crit: Option<Vec<String>>,
+    /// Any other header parameters.
+    #[serde(flatten)]
+    pub additional: BTreeMap<String, Ipld>,
+}
+
+impl ProtectedHeader {
+    /// Decode a header from its JSON representation.
+    pub fn from_json(protected: &[u8]) -> Result<Self, Error> {
+        Ok(serde_json::from_slice(protected)?)
+    }
+
+    /// Encode the header as JSON.
+    ///
+    /// Decoding a header and encoding it again is not guaranteed to reproduce the original
+    /// bytes, the original value should be kept when it must be preserved.
+    pub fn to_json(&self) -> Result<Bytes, Error> {
+        Ok(serde_json::to_vec(self)?.into())
+    }
+
+    /// Decode a header from its base64 url encoded JSON representation.
+    pub fn from_base64(protected: &str) -> Result<Self, Error> {
+        Self::from_json(&base64_url::decode(protected)?)
+    }
+
+    /// Encode the header as base64 url encoded JSON.
+    ///
+    /// Decoding a header and encoding it again is not guaranteed to reproduce the original
+    /// bytes, the original base64 url value should be kept when it must be preserved.
+    pub fn to_base64(&self) -> Result<String, Error> {
+        Ok(self.to_json()?.to_base64())
+    }
+}
+
+impl Signature {
+    /// Decode the protected header, if any.
+    ///
+    /// The header is decoded on each call, the `protected` field is left unchanged.
+    pub fn protected_header(&self) -> Result<Option<ProtectedHeader>, Error> {
+        self.protected
+            .as_deref()
+            .map(ProtectedHeader::from_json)
+            .transpose()
+    }
+}
+
+impl JsonWebEncryption {
+    /// Decode the protected header.
+    ///
+    /// The header is decoded on each call, the `protected` field is left unchanged.
+    /// An absent protected header decodes as an empty header.
+    pub fn protected_header(&self) -> Result<ProtectedHeader, Error> {
+        if self.protected.is_empty() {
+            Ok(ProtectedHeader::default())
+        } else {
+            ProtectedHeader::from_json(&self.protected)
+        }
+    }
+
+    /// Decode the JOSE header of a recipient, the union of the protected header, the shared
+    /// unprotected header and the recipient header.
+    ///
+    /// A header parameter may only occur in one of the three headers.
+    ///
+    /// See https://www.rfc-editor.org/rfc/rfc7516#section-7.2.1
+    pub fn header(&self, recipient: Option<&Recipient>) -> Result<ProtectedHeader, Error> {
+        let mut fields: serde_json::Map<String, serde_json::Value> = if self.protected.is_empty() {
+            serde_json::Map::new()
+        } else {
+            serde_json::from_slice(&self.protected)?
+        };
+        let unprotected = self.unprotected.iter().chain(
+            recipient
+                .into_iter()
+                .flat_map(|recipient| &recipient.header),
+        );
+        for (name, value) in unprotected {
+            if fields
+                .insert(name.clone(), serde_json::to_value(value)?)
+                .is_some()
+            {
+                return Err(Error::DuplicateHeader(name.clone()));
+            }
+        }
+        Ok(serde_json::from_value(serde_json::Value::Object(fields))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn b64(data: &str) -> Bytes {
+        Bytes::from_base64(data).unwrap()
+    }
+
+    #[test]
+    fn decode_jws_header() {
+        let signature = Signature {
+            header: BTreeMap::new(),
+            protected: Some(Bytes::from(br#"{"alg":"EdDSA"}"#.to_vec())),
+            signature: Bytes::from(b"sig".to_vec()),
+        };
+        assert_eq!(
+            signature.protected_header().unwrap(),
+            Some(ProtectedHeader {
+                alg: Some("EdDSA".to_string()),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn decode_jwe_header() {
+        let jwe = JsonWebEncryption {
+            aad: None,
+            ciphertext: b64("3XqLW28NHP-raqW8vMfIHOzko4N3IRaR"),
+            iv: b64("PSWIuAyO8CpevzCL"),
+            protected: b64("eyJhbGciOiJkaXIiLCJlbmMiOiJBMTI4R0NNIn0"),
+            recipients: vec![],
+            tag: b64("WZAMBblhzDCsQWOAKdlkSA"),
+            unprotected: BTreeMap::new(),
+        };
+        let header = jwe.protected_header().unwrap();
+        assert_eq!(header.alg.as_deref(), Some("dir"));
+        assert_eq!(header.enc.as_deref(), Some("A128GCM"));
+        // The original encoding is preserved.
+        assert_eq!(
+            jwe.protected.to_base64(),
+            "eyJhbGciOiJkaXIiLCJlbmMiOiJBMTI4R0NNIn0"
+        );
+    }
+
+    #[test]
+    fn merge_recipient_header() {
+        let mut jwe = JsonWebEncryption {
+            aad: None,
+            ciphertext: b64("3XqLW28NHP-raqW8vMfIHOzko4N3IRaR"),
+            iv: b64("PSWIuAyO8CpevzCL"),
+            protected: b64("eyJlbmMiOiJYQzIwUCJ9"),
+            recipients: vec![],
+            tag: b64("WZAMBblhzDCsQWOAKdlkSA"),
+            unprotected: BTreeMap::from([("typ".to_string(), Ipld::from("JWE"))]),
+        };
+        let recipient = Recipient {
+            encrypted_key: None,
+            header: BTreeMap::from([
+                ("alg".to_string(), Ipld::from("ECDH-ES+XC20PKW")),
+                (
+                    "iv".to_string(),
+                    Ipld::from("WXzvANY_tfso7evHavOf7-bxXUk-xnOP"),
+                ),
+            ]),
+        };
+        let header = jwe.header(Some(&recipient)).unwrap();
+        assert_eq!(header.alg.as_deref(), Some("ECDH-ES+XC20PKW"));
+        assert_eq!(header.enc.as_deref(), Some("XC20P"));
+        assert_eq!(header.typ.as_deref(), Some("JWE"));
+        assert_eq!(
+            header.additional.get("iv"),
+            Some(&Ipld::from("WXzvANY_tfso7evHavOf7-bxXUk-xnOP"))
+        );
+        assert_eq!(jwe.header(None).unwrap().alg, None);
+
+        jwe.unprotected
+            .insert("enc".to_string(), Ipld::from("A256GCM"));
+        assert!(matches!(
+            jwe.header(Some(&recipient)),
+            Err(Error::DuplicateHeader(ref name)) if name == "enc"
+        ));
+    }
+
+    #[test]
+    fn roundtrip_header() {
+        let header = ProtectedHeader {
+            alg: Some("ES256K".to_string()),
+            kid: Some("did:key:zQ3s#zQ3s".to_string()),
+            crit: Some(vec!["exp".to_string()]),
+            additional: BTreeMap::from([("exp".to_string(), Ipld::Integer(1700000000))]),
+            ..Default::default()
+        };
+        let encoded = header.to_base64().unwrap();
+        assert_eq!(ProtectedHeader::from_base64(&encoded).unwrap(), header);
+    }
+
+    #[test]
+    fn invalid_header() {
+        // The JSON value `"alg"` is not an object.
+        assert!(matches!(
+            ProtectedHeader::from_base64("ImFsZyI"),
+            Err(Error::Json(_))
+        ));
+    }
+}
@@ -0,0 +1,391 @@
+//! Conversion to and from the JOSE General and Flattened JSON Serializations.
+//!
+//! Unlike the DAG-JSON encoding these serializations do not contain a `link` field and are
+//! therefore interoperable with other JOSE implementations.
+//!
+//! See https://www.rfc-editor.org/rfc/rfc7515#section-7.2 and
+//! https://www.rfc-editor.org/rfc/rfc7516#section-7.2
+use std::collections::BTreeMap;
+
+use ipld_core::{cid::Cid, ipld::Ipld};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{
+    bytes::Bytes, error::Error, Jose, JsonWebEncryption, JsonWebSignature, Recipient, Signature,
+};
+
+/// Union of the members of the general and flattened JSON serializations of JWS and JWE values.
+///
+/// The DAG-JSON encoding of JOSE values is decoded through it as well, its `link` field is
+/// ignored as the link is derived from the payload.
+#[derive(Default, Debug, Serialize, Deserialize)]
+pub(crate) struct JsonSerialization {
+    // JWS members
+    #[serde(skip_serializing_if = "Option::is_none")]
+    payload: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signatures: Option<Vec<JsonSignature>>,
+
+    // JWE members
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unprotected: Option<BTreeMap<String, Ipld>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recipients: Option<Vec<JsonRecipient>>,
+
+    // Shared and flattened members
+    #[serde(skip_serializing_if = "Option::is_none")]
+    protected: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    header: Option<BTreeMap<String, Ipld>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signature: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encrypted_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    aad: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    iv: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ciphertext: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tag: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct JsonSignature {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    protected: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    header: Option<BTreeMap<String, Ipld>>,
+    signature: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct JsonRecipient {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    header: Option<BTreeMap<String, Ipld>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encrypted_key: Option<String>,
+}
+
+impl Jose {
+    /// Parse a JOSE value from either its general or flattened JSON serialization.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        serde_json::from_str::<JsonSerialization>(json)?.try_into()
+    }
+
+    /// Serialize the JOSE value using the general JSON serialization.
+    pub fn to_general_json(&self) -> Result<String, Error> {
+        match self {
+            Jose::Signature(jws) => jws.to_general_json(),
+            Jose::Encryption(jwe) => jwe.to_general_json(),
+        }
+    }
+
+    /// Serialize the JOSE value using the flattened JSON serialization.
+    pub fn to_flattened_json(&self) -> Result<String, Error> {
+        match self {
+            Jose::Signature(jws) => jws.to_flattened_json(),
+            Jose::Encryption(jwe) => jwe.to_flattened_json(),
+        }
+    }
+}
+
+impl JsonWebSignature {
+    /// Parse a JWS from either its general or flattened JSON serialization.
+    ///
+    /// The payload must be a CID.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        serde_json::from_str::<JsonSerialization>(json)?.try_into()
+    }
+
+    /// Serialize the JWS using the general JSON serialization.
+    pub fn to_general_json(&self) -> Result<String, Error> {
+        self.check_link()?;
+        Ok(serde_json::to_string(&JsonSerialization {
+            payload: Some(self.payload.to_base64()),
+            signatures: Some(self.signatures.iter().map(JsonSignature::from).collect()),
+            ..Default::default()
+        })?)
+    }
+
+    /// Serialize the JWS using the flattened JSON serialization.
+    ///
+    /// Only a JWS with a single signature can be flattened.
+    pub fn to_flattened_json(&self) -> Result<String, Error> {
+        self.check_link()?;
+        let signature = match self.signatures.as_slice() {
+            [signature] => JsonSignature::from(signature),
+            [] => return Err(Error::NotFlattened("no signatures")),
+            _ => return Err(Error::NotFlattened("multiple signatures")),
+        };
+        Ok(serde_json::to_string(&JsonSerialization {
+            payload: Some(self.payload.to_base64()),
+            protected: signature.protected,
+            header: signature.header,
+            signature: Some(signature.signature),
+            ..Default::default()
+        })?)
+    }
+}
+
+impl JsonWebEncryption {
+    /// Parse a JWE from either its general or flattened JSON serialization.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        serde_json::from_str::<JsonSerialization>(json)?.try_into()
+    }
+
+    /// Serialize the JWE using the general JSON serialization.
+    pub fn to_general_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(&JsonSerialization {
+            recipients: if self.recipients.is_empty() {
+                None
+            } else {
+                Some(self.recipients.iter().map(JsonRecipient::from).collect())
+            },
+            ..self.shared_members()
+        })?)
+    }
+
+    /// Serialize the JWE using the flattened JSON serialization.
+    ///
+    /// Only a JWE with at most one recipient can be flattened.
+    pub fn to_flattened_json(&self) -> Result<String, Error> {
+        let recipient = match self.recipients.as_slice() {
+            [] => JsonRecipient {
+                header: None,
+                encrypted_key: None,
+            },
+            [recipient] => JsonRecipient::from(recipient),
+            _ => return Err(Error::NotFlattened("multiple recipients")),
+        };
+        Ok(serde_json::to_string(&JsonSerialization {
+            header: recipient.header,
+            encrypted_key: recipient.encrypted_key,
+            ..self.shared_members()
+        })?)
+    }
+
+    /// Members common to both the general and flattened serializations.
+    fn shared_members(&self) -> JsonSerialization {
+        JsonSerialization {
+            unprotected: if self.unprotected.is_empty() {
+                None
+            } else {
+                Some(self.unprotected.to_owned())
+            },
+            protected: if self.protected.is_empty() {
+                None
+            } else {
+                Some(self.protected.to_base64())
+            },
+            aad: self.aad.as_ref().map(Bytes::to_base64),
+            iv: Some(self.iv.to_base64()),
+            ciphertext: Some(self.ciphertext.to_base64()),
+            tag: Some(self.tag.to_base64()),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<JsonSerialization> for Jose {
+    type Error = Error;
+
+    fn try_from(value: JsonSerialization) -> Result<Self, Self::Error> {
+        Ok(match value.payload {
+            Some(_) => Jose::Signature(value.try_into()?),
+            None => Jose::Encryption(value.try_into()?),
+        })
+    }
+}
+
+impl TryFrom<JsonSerialization> for JsonWebSignature {
+    type Error = Error;
+
+    fn try_from(value: JsonSerialization) -> Result<Self, Self::Error> {
+        let payload = Bytes::from_base64(&value.payload.ok_or(Error::NotJws)?)?;
+        let link = Cid::try_from(payload.as_slice())?;
+        let signatures = match value.signatures {
+            Some(signatures) => {
+                if value.protected.is_some() || value.header.is_some() || value.signature.is_some()
+                {
+                    return Err(Error::InvalidJsonSerialization(
+                        "both general and flattened signature members",
+                    ));
+                }
+                signatures
+            }
+            None => vec![JsonSignature {
+                protected: value.protected,
+                header: value.header,
+                signature: value
+                    .signature
+                    .ok_or(Error::InvalidJsonSerialization("missing signature"))?,
+            }],
+        };
+        Ok(Self {
+            link,
+            payload,
+            signatures: signatures
+                .into_iter()
+                .map(Signature::try_from)
+                .collect::<Result<Vec<Signature>, Self::Error>>()?,
+        })
+    }
+}
+
+impl TryFrom<JsonSerialization> for JsonWebEncryption {
+    type Error = Error;
+
+    fn try_from(value: JsonSerialization) -> Result<Self, Self::Error> {
+        let recipients = match value.recipients {
+            Some(recipients) => {
+                if value.header.is_some() || value.encrypted_key.is_some() {
+                    return Err(Error::InvalidJsonSerialization(
+                        "both general and flattened recipient members",
+                    ));
+                }
+                recipients
+            }
+            None if value.header.is_some() || value.encrypted_key.is_some() => {
+                vec![JsonRecipient {
+                    header: value.header,
+                    encrypted_key: value.encrypted_key,
+                }]
+            }
+            None => vec![],
+        };
+        Ok(Self {
+            aad: value.aad.as_deref().map(Bytes::from_base64).transpose()?,
+            ciphertext: Bytes::from_base64(&value.ciphertext.ok_or(Error::NotJwe)?)?,
+            iv: Bytes::from_base64(&value.iv.ok_or(Error::NotJwe)?)?,
+            protected: Bytes::from_base64(&value.protected.unwrap_or_default())?,
+            recipients: recipients
+                .into_iter()
+                .map(Recipient::try_from)
+                .collect::<Result<Vec<Recipient>, Self::Error>>()?,
+            tag: Bytes::from_base64(&value.tag.ok_or(Error::NotJwe)?)?,
+            unprotected: value.unprotected.unwrap_or_default(),
+        })
+    }
+}
+
+impl<'a> From<&'a Signature> for JsonSignature {
+    fn from(value: &'a Signature) -> Self {
+        Self {
+            protected: value.protected.as_ref().map(Bytes::to_base64),
+            header: if value.header.is_empty() {
+                None
+            } else {
+                Some(value.header.to_owned())
+            },
+            signature: value.signature.to_base64(),
+        }
+    }
+}
+
+impl TryFrom<JsonSignature> for Signature {
+    type Error = Error;
+
+    fn try_from(value: JsonSignature) -> Result<Self, Self::Error> {
+        Ok(Self {
+            header: value.header.unwrap_or_default(),
+            protected: value
+                .protected
+                .as_deref()
+                .map(Bytes::from_base64)
+                .transpose()?,
+            signature: Bytes::from_base64(&value.signature)?,
+        })
+    }
+}
+
+impl<'a> From<&'a Recipient> for JsonRecipient {
+    fn from(value: &'a Recipient) -> Self {
+        Self {
+            header: if value.header.is_empty() {
+                None
+            } else {
+                Some(value.header.to_owned())
+            },
+            encrypted_key: value.encrypted_key.as_ref().map(Bytes::to_base64),
+        }
+    }
+}
+
+impl TryFrom<JsonRecipient> for Recipient {
+    type Error = Error;
+
+    fn try_from(value: JsonRecipient) -> Result<Self, Self::Error> {
+        Ok(Self {
+            encrypted_key: value
+                .encrypted_key
+                .as_deref()
+                .map(Bytes::from_base64)
+                .transpose()?,
+            header: value.header.unwrap_or_default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GENERAL_JWS: &str = r#"{"payload":"AXESIIlVZVHDkmZ5zFLHLhgqVhkFakcnQJ7pOibQWtcnyhH0","signatures":[{"protected":"eyJhbGciOiJFZERTQSJ9","signature":"-_9J5OZcl5lVuRlgI1NJEzc0FqEb6_2yVskUaQPducRQ4oe-N5ynCl57wDm4SPtm1L1bltrphpQeBOeWjVW1BQ"}]}"#;
+    const FLATTENED_JWS: &str = r#"{"payload":"AXESIIlVZVHDkmZ5zFLHLhgqVhkFakcnQJ7pOibQWtcnyhH0","protected":"eyJhbGciOiJFZERTQSJ9","signature":"-_9J5OZcl5lVuRlgI1NJEzc0FqEb6_2yVskUaQPducRQ4oe-N5ynCl57wDm4SPtm1L1bltrphpQeBOeWjVW1BQ"}"#;
+    const GENERAL_JWE: &str = r#"{"recipients":[{"header":{"alg":"A128KW","kid":"7"},"encrypted_key":"6KB707dM9YTIgHtLvtgWQ8mKwboJW3of9locizkDTHzBC2IlrT1oOQ"}],"protected":"eyJlbmMiOiJBMTI4R0NNIn0","iv":"AxY8DCtDaGlsbGljb3RoZQ","ciphertext":"KDlTtXchhZTGufMYmOYGS4HffxPSUrfmqCHXaI9wOGY","tag":"U0m_YmjN04DJvceFICbCVQ"}"#;
+    const FLATTENED_JWE: &str = r#"{"protected":"eyJlbmMiOiJBMTI4R0NNIn0","header":{"alg":"A128KW","kid":"7"},"encrypted_key":"6KB707dM9YTIgHtLvtgWQ8mKwboJW3of9locizkDTHzBC2IlrT1oOQ","iv":"AxY8DCtDaGlsbGljb3RoZQ","ciphertext":"KDlTtXchhZTGufMYmOYGS4HffxPSUrfmqCHXaI9wOGY","tag":"U0m_YmjN04DJvceFICbCVQ"}"#;
+
+    #[test]
+    fn roundtrip_jws() {
+        let general = Jose::from_json(GENERAL_JWS).unwrap();
+        let flattened = Jose::from_json(FLATTENED_JWS).unwrap();
+        assert_eq!(general, flattened);
+        let Jose::Signature(jws) = &general else {
+            panic!("expected a JWS value")
+        };
+        assert_eq!(
+            jws.link.to_string(),
+            "bafyreiejkvsvdq4smz44yuwhfymcuvqzavveoj2at3utujwqlllspsqr6q"
+        );
+        assert_eq!(general.to_general_json().unwrap(), GENERAL_JWS);
+        assert_eq!(general.to_flattened_json().unwrap(), FLATTENED_JWS);
+    }
+
+    #[test]
+    fn roundtrip_jwe() {
+        let general = Jose::from_json(GENERAL_JWE).unwrap();
+        let flattened = Jose::from_json(FLATTENED_JWE).unwrap();
+        assert_eq!(general, flattened);
+        let Jose::Encryption(jwe) = &general else {
+            panic!("expected a JWE value")
+        };
+        assert_eq!(jwe.recipients.len(), 1);
+        assert_eq!(general.to_general_json().unwrap(), GENERAL_JWE);
+        assert_eq!(general.to_flattened_json().unwrap(), FLATTENED_JWE);
+    }
+
+    #[test]
+    fn invalid_json() {
+        // Mixed general and flattened members
+        let mixed = GENERAL_JWS.replace(r#""signatures""#, r#""signature":"c2ln","signatures""#);
+        assert!(matches!(
+            Jose::from_json(&mixed),
+            Err(Error::InvalidJsonSerialization(_))
+        ));
+        // Invalid base64 url data
+        let invalid = FLATTENED_JWE.replace("AxY8DCtDaGlsbGljb3RoZQ", "not base64!");
+        assert!(matches!(
+            Jose::from_json(&invalid),
+            Err(Error::InvalidBase64Url(_))
+        ));
+        // Multiple signatures cannot be flattened
+        let mut jws = JsonWebSignature::from_json(GENERAL_JWS).unwrap();
+        jws.signatures.push(jws.signatures[0].clone());
+        assert!(matches!(
+            jws.to_flattened_json(),
+            Err(Error::NotFlattened(_))
+        ));
+    }
+}
@@ -0,0 +1,763 @@
+//! Encryption and decryption of JSON Web Encryption values.
+//!
+//! Content encryption keys are encrypted for recipients by implementations of the
+//! [`KeyEncrypter`] trait and recovered by implementations of the [`KeyDecrypter`] trait.
+use std::collections::BTreeMap;
+
+use aes_gcm::{
+    aead::{generic_array::typenum::Unsigned, AeadCore, AeadInPlace, KeyInit},
+    Aes128Gcm, Aes256Gcm,
+};
+use chacha20poly1305::XChaCha20Poly1305;
+use ipld_core::ipld::Ipld;
+
+use crate::{bytes::Bytes, cleartext, error::Error, JsonWebEncryption, ProtectedHeader, Recipient};
+
+/// A JWE content encryption algorithm.
+///
+/// See https://www.rfc-editor.org/rfc/rfc7518#section-5.3 and
+/// https://datatracker.ietf.org/doc/html/draft-amringer-jose-chacha-02
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContentEncryption {
+    /// AES GCM using a 128 bit key.
+    A128Gcm,
+    /// AES GCM using a 256 bit key.
+    A256Gcm,
+    /// XChaCha20-Poly1305 using a 256 bit key.
+    XC20P,
+}
+
+impl ContentEncryption {
+    /// Find the algorithm for an `enc` header parameter value.
+    pub fn from_name(enc: &str) -> Result<Self, Error> {
+        match enc {
+            "A128GCM" => Ok(Self::A128Gcm),
+            "A256GCM" => Ok(Self::A256Gcm),
+            "XC20P" => Ok(Self::XC20P),
+            enc => Err(Error::UnsupportedAlgorithm(enc.to_string())),
+        }
+    }
+
+    /// The `enc` header parameter value of the algorithm.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::A128Gcm => "A128GCM",
+            Self::A256Gcm => "A256GCM",
+            Self::XC20P => "XC20P",
+        }
+    }
+
+    /// The length in bytes of the content encryption key.
+    pub fn key_len(self) -> usize {
+        match self {
+            Self::A128Gcm => 16,
+            Self::A256Gcm | Self::XC20P => 32,
+        }
+    }
+
+    /// The length in bytes of the initialization vector.
+    pub fn iv_len(self) -> usize {
+        match self {
+            Self::A128Gcm | Self::A256Gcm => 12,
+            Self::XC20P => 24,
+        }
+    }
+
+    /// Encrypt the plaintext returning the ciphertext and authentication tag.
+    pub(crate) fn encrypt(
+        self,
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+        plaintext: &[u8],
+    ) -> Result<(Vec<u8>, Vec<u8>), Error> {
+        match self {
+            Self::A128Gcm => seal::<Aes128Gcm>(self, key, iv, aad, plaintext),
+            Self::A256Gcm => seal::<Aes256Gcm>(self, key, iv, aad, plaintext),
+            Self::XC20P => seal::<XChaCha20Poly1305>(self, key, iv, aad, plaintext),
+        }
+    }
+
+    /// Authenticate and decrypt the ciphertext in place.
+    ///
+    /// The tag is verified before the buffer is modified, so the ciphertext is left unchanged
+    /// when decryption fails.
+    pub(crate) fn decrypt_in_place(
+        self,
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+        buffer: &mut [u8],
+        tag: &[u8],
+    ) -> Result<(), Error> {
+        match self {
+            Self::A128Gcm => open::<Aes128Gcm>(self, key, iv, aad, buffer, tag),
+            Self::A256Gcm => open::<Aes256Gcm>(self, key, iv, aad, buffer, tag),
+            Self::XC20P => open::<XChaCha20Poly1305>(self, key, iv, aad, buffer, tag),
+        }
+    }
+}
+
+fn seal<C: AeadInPlace + KeyInit>(
+    enc: ContentEncryption,
+    key: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+    plaintext: &[u8],
+) -> Result<(Vec<u8>, Vec<u8>), Error> {
+    let cipher = C::new_from_slice(key).map_err(|_| Error::InvalidKey(enc.name()))?;
+    if iv.len() != <C as AeadCore>::NonceSize::USIZE {
+        return Err(Error::InvalidJwe("iv length"));
+    }
+    let mut buffer = plaintext.to_vec();
+    let tag = cipher
+        .encrypt_in_place_detached(iv.into(), aad, &mut buffer)
+        .map_err(|_| Error::Encryption)?;
+    Ok((buffer, tag.to_vec()))
+}
+
+fn open<C: AeadInPlace + KeyInit>(
+    enc: ContentEncryption,
+    key: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+    buffer: &mut [u8],
+    tag: &[u8],
+) -> Result<(), Error> {
+    let cipher = C::new_from_slice(key).map_err(|_| Error::InvalidKey(enc.name()))?;
+    if iv.len() != <C as AeadCore>::NonceSize::USIZE {
+        return Err(Error::InvalidJwe("iv length"));
+    }
+    if tag.len() != <C as AeadCore>::TagSize::USIZE {
+        return Err(Error::InvalidJwe("tag length"));
+    }
+    cipher
+        .decrypt_in_place_detached(iv.into(), aad, buffer, tag.into())
+        .map_err(|_| Error::Decryption)
+}
+
+/// Generate random bytes, used for content encryption keys and initialization vectors.
+pub(crate) fn random_bytes(len: usize) -> Result<Vec<u8>, Error> {
+    let mut bytes = vec![0; len];
+    getrandom::getrandom(&mut bytes).map_err(|_| Error::Random)?;
+    Ok(bytes)
+}
+
+/// Encrypts the content encryption key of a JWE for a recipient.
+pub trait KeyEncrypter {
+    /// The `alg` header parameter value of the recipient.
+    fn algorithm(&self) -> &str;
+
+    /// The content encryption key determined by the key management algorithm, if any.
+    ///
+    /// Algorithms such as `dir` use a known key instead of a random content encryption key and
+    /// can only be used for a single recipient.
+    fn direct_key(&self, _enc: ContentEncryption) -> Result<Option<Vec<u8>>, Error> {
+        Ok(None)
+    }
+
+    /// Encrypt the content encryption key for the recipient, returning the encrypted key.
+    ///
+    /// Parameters the recipient needs to recover the key, such as an ephemeral public key, are
+    /// added to its header. The authentication tag of the content is provided for algorithms
+    /// that bind it into the key derivation.
+    fn encrypt_key(
+        &self,
+        cek: &[u8],
+        header: &mut BTreeMap<String, Ipld>,
+        tag: &[u8],
+    ) -> Result<Vec<u8>, Error>;
+}
+
+/// Recovers the content encryption key of a JWE recipient.
+pub trait KeyDecrypter {
+    /// Recover the content encryption key from the encrypted key of a recipient.
+    ///
+    /// The header is the complete JOSE header of the recipient, see
+    /// [`JsonWebEncryption::header`]. The authentication tag of the content is provided for
+    /// algorithms that bind it into the key derivation.
+    ///
+    /// An [`Error::UnsupportedAlgorithm`] error is returned when the `alg` of the recipient is not
+    /// handled by the decrypter.
+    fn decrypt_key(
+        &self,
+        header: &ProtectedHeader,
+        encrypted_key: &[u8],
+        tag: &[u8],
+    ) -> Result<Vec<u8>, Error>;
+}
+
+/// A shared symmetric key used directly as the content encryption key, the `dir` algorithm.
+///
+/// See https://www.rfc-editor.org/rfc/rfc7518#section-4.5
+pub struct DirectKey(Vec<u8>);
+
+impl DirectKey {
+    /// Create a direct key from the content encryption key bytes.
+    pub fn new(key: &[u8]) -> Self {
+        Self(key.to_vec())
+    }
+}
+
+impl KeyEncrypter for DirectKey {
+    fn algorithm(&self) -> &str {
+        "dir"
+    }
+
+    fn direct_key(&self, enc: ContentEncryption) -> Result<Option<Vec<u8>>, Error> {
+        if self.0.len() != enc.key_len() {
+            return Err(Error::InvalidKey(enc.name()));
+        }
+        Ok(Some(self.0.clone()))
+    }
+
+    fn encrypt_key(
+        &self,
+        _cek: &[u8],
+        _header: &mut BTreeMap<String, Ipld>,
+        _tag: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        Ok(Vec::new())
+    }
+}
+
+impl KeyDecrypter for DirectKey {
+    fn decrypt_key(
+        &self,
+        header: &ProtectedHeader,
+        encrypted_key: &[u8],
+        _tag: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        match header.alg.as_deref() {
+            Some("dir") => {}
+            Some(alg) => return Err(Error::UnsupportedAlgorithm(alg.to_string())),
+            None => return Err(Error::MissingAlgorithm),
+        }
+        if !encrypted_key.is_empty() {
+            return Err(Error::InvalidJwe(
+                "encrypted key used with direct encryption",
+            ));
+        }
+        Ok(self.0.clone())
+    }
+}
+
+impl JsonWebEncryption {
+    /// The additional authenticated data of the content encryption,
+    /// `ASCII(base64url(protected))`, followed by `'.' || ASCII(base64url(aad))` when `aad` is
+    /// present.
+    ///
+    /// See https://www.rfc-editor.org/rfc/rfc7516#section-5.1
+    pub fn additional_authenticated_data(&self) -> Vec<u8> {
+        let mut aad = self.protected.to_base64().into_bytes();
+        if let Some(external) = &self.aad {
+            aad.push(b'.');
+            aad.extend_from_slice(external.to_base64().as_bytes());
+        }
+        aad
+    }
+
+    /// Decrypt the content using the first recipient whose content encryption key can be
+    /// recovered by the decrypter.
+    ///
+    /// A JWE without recipients is treated as having a single recipient described by the
+    /// protected and shared unprotected headers, as used by direct encryption.
+    pub fn decrypt(&self, decrypter: &dyn KeyDecrypter) -> Result<Vec<u8>, Error> {
+        let mut buffer = self.ciphertext.to_vec();
+        self.decrypt_in_place(decrypter, &mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Decrypt the ciphertext held in the buffer in place, using the other fields of the JWE.
+    ///
+    /// The `ciphertext` field is not used, which allows decrypting a ciphertext streamed from
+    /// a block without copying it.
+    pub(crate) fn decrypt_in_place(
+        &self,
+        decrypter: &dyn KeyDecrypter,
+        buffer: &mut [u8],
+    ) -> Result<(), Error> {
+        let recipients: Vec<Option<&Recipient>> = if self.recipients.is_empty() {
+            vec![None]
+        } else {
+            self.recipients.iter().map(Some).collect()
+        };
+        let mut error = None;
+        for recipient in recipients {
+            match self.decrypt_recipient(recipient, decrypter, buffer) {
+                Ok(()) => return Ok(()),
+                // Prefer reporting a failure to decrypt over recipients the decrypter does not
+                // handle.
+                Err(err) => {
+                    if matches!(error, None | Some(Error::UnsupportedAlgorithm(_))) {
+                        error = Some(err);
+                    }
+                }
+            }
+        }
+        Err(error.expect("a JWE has at least one recipient"))
+    }
+
+    /// Decrypt the content and decode it as a DAG-CBOR encoded IPLD node.
+    pub fn decrypt_ipld(&self, decrypter: &dyn KeyDecrypter) -> Result<Ipld, Error> {
+        cleartext::decode(&self.decrypt(decrypter)?, false)
+    }
+
+    /// Decrypt the content and decode it as a DAG-CBOR encoded IPLD node followed by zero
+    /// padding, the cleartext format of dag-jose-utils.
+    ///
+    /// Unpadded content is accepted as well.
+    pub fn decrypt_padded_ipld(&self, decrypter: &dyn KeyDecrypter) -> Result<Ipld, Error> {
+        cleartext::decode(&self.decrypt(decrypter)?, true)
+    }
+
+    fn decrypt_recipient(
+        &self,
+        recipient: Option<&Recipient>,
+        decrypter: &dyn KeyDecrypter,
+        buffer: &mut [u8],
+    ) -> Result<(), Error> {
+        let header = self.header(recipient)?;
+        // No extensions are understood, so any critical extension must be rejected.
+        if let Some(name) = header.crit.iter().flatten().next() {
+            return Err(Error::UnsupportedCritical(name.clone()));
+        }
+        let enc = ContentEncryption::from_name(
+            header
+                .enc
+                .as_deref()
+                .ok_or(Error::MissingContentEncryption)?,
+        )?;
+        let encrypted_key = recipient
+            .and_then(|r| r.encrypted_key.as_deref())
+            .unwrap_or_default();
+        let key = decrypter.decrypt_key(&header, encrypted_key, &self.tag)?;
+        enc.decrypt_in_place(
+            &key,
+            &self.iv,
+            &self.additional_authenticated_data(),
+            buffer,
+            &self.tag,
+        )
+    }
+}
+
+/// Builds an encrypted [`JsonWebEncryption`] for a set of recipients.
+///
+/// A random content encryption key and initialization vector are generated for each value,
+/// unless the key management algorithm of the recipient determines the key.
+///
+/// ```
+/// use dag_jose::{ContentEncryption, DirectKey, JweBuilder};
+///
+/// let key = DirectKey::new(&[7; 32]);
+/// let jwe = JweBuilder::new(ContentEncryption::XC20P)
+///     .recipient(&key)
+///     .build(b"secret")
+///     .unwrap();
+/// assert_eq!(jwe.decrypt(&key).unwrap(), b"secret");
+/// ```
+pub struct JweBuilder<'a> {
+    enc: ContentEncryption,
+    protected: ProtectedHeader,
+    unprotected: BTreeMap<String, Ipld>,
+    aad: Option<Vec<u8>>,
+    recipients: Vec<(&'a dyn KeyEncrypter, BTreeMap<String, Ipld>)>,
+}
+
+impl<'a> JweBuilder<'a> {
+    /// The default block size the cleartext of padded IPLD nodes is padded to, as used by
+    /// dag-jose-utils.
+    pub const DEFAULT_BLOCK_SIZE: usize = 24;
+
+    /// Create a builder encrypting the content with the algorithm.
+    pub fn new(enc: ContentEncryption) -> Self {
+        Self {
+            enc,
+            protected: ProtectedHeader::default(),
+            unprotected: BTreeMap::new(),
+            aad: None,
+            recipients: Vec::new(),
+        }
+    }
+
+    /// Set the protected header.
+    ///
+    /// The `enc` parameter is set to the content encryption algorithm.
+    pub fn protected(mut self, protected: ProtectedHeader) -> Self {
+        self.protected = protected;
+        self
+    }
+
+    /// Set the shared unprotected header.
+    pub fn unprotected(mut self, unprotected: BTreeMap<String, Ipld>) -> Self {
+        self.unprotected = unprotected;
+        self
+    }
+
+    /// Set the external additional authenticated data.
+    pub fn aad(mut self, aad: &[u8]) -> Self {
+        self.aad = Some(aad.to_vec());
+        self
+    }
+
+    /// Add a recipient whose content encryption key is encrypted by the encrypter.
+    pub fn recipient(self, encrypter: &'a dyn KeyEncrypter) -> Self {
+        self.recipient_with_header(encrypter, BTreeMap::new())
+    }
+
+    /// Add a recipient with additional recipient header parameters.
+    ///
+    /// The `alg` parameter of the recipient header is set to the algorithm of the encrypter, a
+    /// different `alg` in the header is a [`Error::DuplicateHeader`] when building.
+    pub fn recipient_with_header(
+        mut self,
+        encrypter: &'a dyn KeyEncrypter,
+        header: BTreeMap<String, Ipld>,
+    ) -> Self {
+        self.recipients.push((encrypter, header));
+        self
+    }
+
+    /// Encrypt the plaintext for each recipient.
+    ///
+    /// When the key management algorithm determines the content encryption key, such as with
+    /// `dir`, its `alg` is set in the protected header and the JWE has no recipients.
+    pub fn build(mut self, plaintext: &[u8]) -> Result<JsonWebEncryption, Error> {
+        let mut direct = None;
+        for (encrypter, header) in &self.recipients {
+            if let Some(key) = encrypter.direct_key(self.enc)? {
+                if self.recipients.len() > 1 {
+                    return Err(Error::InvalidJwe(
+                        "direct key management with multiple recipients",
+                    ));
+                }
+                self.protected.alg = Some(encrypter.algorithm().to_string());
+                for (name, value) in header {
+                    if self
+                        .unprotected
+                        .insert(name.clone(), value.clone())
+                        .is_some()
+                    {
+                        return Err(Error::DuplicateHeader(name.clone()));
+                    }
+                }
+                direct = Some(key);
+            }
+        }
+        let is_direct = direct.is_some();
+        let cek = match direct {
+            Some(key) => key,
+            None if self.recipients.is_empty() => return Err(Error::MissingRecipient),
+            None => random_bytes(self.enc.key_len())?,
+        };
+        self.protected.enc = Some(self.enc.name().to_string());
+
+        let iv = random_bytes(self.enc.iv_len())?;
+        let mut jwe = JsonWebEncryption {
+            aad: self.aad.map(Bytes::from),
+            ciphertext: Bytes::default(),
+            iv: Bytes::from(iv),
+            protected: self.protected.to_json()?,
+            recipients: Vec::new(),
+            tag: Bytes::default(),
+            unprotected: self.unprotected,
+        };
+        let (ciphertext, tag) = self.enc.encrypt(
+            &cek,
+            &jwe.iv,
+            &jwe.additional_authenticated_data(),
+            plaintext,
+        )?;
+        jwe.ciphertext = ciphertext.into();
+        jwe.tag = tag.into();
+
+        if !is_direct {
+            for (encrypter, mut header) in self.recipients {
+                let alg = Ipld::String(encrypter.algorithm().to_string());
+                if header.get("alg").is_some_and(|value| *value != alg) {
+                    return Err(Error::DuplicateHeader("alg".to_string()));
+                }
+                header.insert("alg".to_string(), alg);
+                let encrypted_key = encrypter.encrypt_key(&cek, &mut header, &jwe.tag)?;
+                jwe.recipients.push(Recipient {
+                    encrypted_key: Some(encrypted_key.into()),
+                    header,
+                });
+            }
+        }
+        // Check that no header parameter occurs more than once for a recipient.
+        match jwe.recipients.as_slice() {
+            [] => jwe.header(None).map(|_| ())?,
+            recipients => recipients
+                .iter()
+                .try_for_each(|recipient| jwe.header(Some(recipient)).map(|_| ()))?,
+        }
+        Ok(jwe)
+    }
+
+    /// Encrypt the DAG-CBOR encoding of the IPLD node for each recipient.
+    pub fn build_ipld(self, ipld: &Ipld) -> Result<JsonWebEncryption, Error> {
+        self.build(&cleartext::encode(ipld, 0)?)
+    }
+
+    /// Encrypt the DAG-CBOR encoding of the IPLD node padded with zero bytes to a multiple of
+    /// the block size, see [`Self::DEFAULT_BLOCK_SIZE`].
+    ///
+    /// The content is decrypted with [`JsonWebEncryption::decrypt_padded_ipld`].
+    pub fn build_padded_ipld(
+        self,
+        ipld: &Ipld,
+        block_size: usize,
+    ) -> Result<JsonWebEncryption, Error> {
+        self.build(&cleartext::encode(ipld, block_size)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ipld_core::codec::Codec;
+
+    use super::*;
+    use crate::DagJoseCodec;
+
+    const PLAINTEXT: &[u8] = b"The true sign of intelligence is not knowledge but imagination.";
+
+    fn jwe(protected: &str, ciphertext: &str, iv: &str, tag: &str) -> JsonWebEncryption {
+        JsonWebEncryption {
+            aad: None,
+            ciphertext: Bytes::from_base64(ciphertext).unwrap(),
+            iv: Bytes::from_base64(iv).unwrap(),
+            protected: Bytes::from_base64(protected).unwrap(),
+            recipients: vec![],
+            tag: Bytes::from_base64(tag).unwrap(),
+            unprotected: BTreeMap::new(),
+        }
+    }
+
+    fn key(len: u8) -> DirectKey {
+        DirectKey::new(&(0..len).collect::<Vec<u8>>())
+    }
+
+    #[test]
+    fn decrypt_a128gcm() {
+        let mut jwe = jwe(
+            "eyJhbGciOiJkaXIiLCJlbmMiOiJBMTI4R0NNIn0",
+            "xwTC7hJpgjFroQjtWIMfbpNPdJI2gZGfgUSfTnFkyx-lyrSD1OvZjDeYbIOn7Pfwues0mWnmURJALwa7xSI4",
+            "AAECAwQFBgcICQoL",
+            "diNBnNERahb8zi1y_fQcBQ",
+        );
+        assert_eq!(jwe.decrypt(&key(16)).unwrap(), PLAINTEXT);
+        assert!(matches!(
+            jwe.decrypt(&key(32)),
+            Err(Error::InvalidKey("A128GCM"))
+        ));
+        jwe.tag = Bytes::from_base64("eiNBnNERahb8zi1y_fQcBQ").unwrap();
+        assert!(matches!(jwe.decrypt(&key(16)), Err(Error::Decryption)));
+    }
+
+    #[test]
+    fn decrypt_a256gcm_with_aad() {
+        let mut jwe = jwe(
+            "eyJhbGciOiJkaXIiLCJlbmMiOiJBMjU2R0NNIn0",
+            "E2qzO7GXt36tMv7s38kXC6O_6UCVFzMVXwKL5nhJacEhfsGIj6p89wPIGonv4ghamy1A5De3xLNR9l5wd43b",
+            "AAECAwQFBgcICQoL",
+            "6t42aQ38FKV973Hop_FRXg",
+        );
+        // The external additional authenticated data is required.
+        assert!(matches!(jwe.decrypt(&key(32)), Err(Error::Decryption)));
+        jwe.aad = Some(Bytes::from(b"external".to_vec()));
+        assert_eq!(jwe.decrypt(&key(32)).unwrap(), PLAINTEXT);
+    }
+
+    #[test]
+    fn decrypt_xc20p() {
+        let jwe = jwe(
+            "eyJhbGciOiJkaXIiLCJlbmMiOiJYQzIwUCJ9",
+            "yqpqX-Sg-MsTN0-ppXLHjmsuRtGYunGCGH9R1ViNnCp-aNmQYQxnC6L5yz2oRHMABM7CMCoVnHBp-udU4Ilq",
+            "AAECAwQFBgcICQoLDA0ODxAREhMUFRYX",
+            "cXR6BNIYyXlKc4XgxphcGQ",
+        );
+        assert_eq!(jwe.decrypt(&key(32)).unwrap(), PLAINTEXT);
+    }
+
+    #[test]
+    fn reject_unsupported() {
+        // {"alg":"dir","enc":"A128CBC-HS256"}
+        let jwe = jwe(
+            "eyJhbGciOiJkaXIiLCJlbmMiOiJBMTI4Q0JDLUhTMjU2In0",
+            "3XqLW28NHP-raqW8vMfIHOzko4N3IRaR",
+            "PSWIuAyO8CpevzCL",
+            "WZAMBblhzDCsQWOAKdlkSA",
+        );
+        assert!(matches!(
+            jwe.decrypt(&key(32)),
+            Err(Error::UnsupportedAlgorithm(ref enc)) if enc == "A128CBC-HS256"
+        ));
+        // {"alg":"A128KW","enc":"A128GCM"}
+        let jwe = JsonWebEncryption {
+            protected: Bytes::from(br#"{"alg":"A128KW","enc":"A128GCM"}"#.to_vec()),
+            ..jwe
+        };
+        assert!(matches!(
+            jwe.decrypt(&key(16)),
+            Err(Error::UnsupportedAlgorithm(ref alg)) if alg == "A128KW"
+        ));
+    }
+
+    // Key encryption that XORs the content encryption key with a fixed byte.
+    struct XorKey(u8);
+
+    impl KeyEncrypter for XorKey {
+        fn algorithm(&self) -> &str {
+            "XOR"
+        }
+
+        fn encrypt_key(
+            &self,
+            cek: &[u8],
+            header: &mut BTreeMap<String, Ipld>,
+            _tag: &[u8],
+        ) -> Result<Vec<u8>, Error> {
+            header.insert("xor".to_string(), Ipld::Integer(self.0.into()));
+            Ok(cek.iter().map(|b| b ^ self.0).collect())
+        }
+    }
+
+    impl KeyDecrypter for XorKey {
+        fn decrypt_key(
+            &self,
+            header: &ProtectedHeader,
+            encrypted_key: &[u8],
+            _tag: &[u8],
+        ) -> Result<Vec<u8>, Error> {
+            if header.alg.as_deref() != Some("XOR") {
+                return Err(Error::UnsupportedAlgorithm(
+                    header.alg.clone().unwrap_or_default(),
+                ));
+            }
+            if header.additional.get("xor") != Some(&Ipld::Integer(self.0.into())) {
+                return Err(Error::Decryption);
+            }
+            Ok(encrypted_key.iter().map(|b| b ^ self.0).collect())
+        }
+    }
+
+    #[test]
+    fn build_direct() {
+        for (enc, len) in [
+            (ContentEncryption::A128Gcm, 16),
+            (ContentEncryption::A256Gcm, 32),
+            (ContentEncryption::XC20P, 32),
+        ] {
+            let jwe = JweBuilder::new(enc)
+                .aad(b"external")
+                .recipient(&key(len))
+                .build(PLAINTEXT)
+                .unwrap();
+            assert!(jwe.recipients.is_empty());
+            assert_eq!(jwe.aad.as_deref(), Some(&b"external"[..]));
+            let header = jwe.protected_header().unwrap();
+            assert_eq!(header.alg.as_deref(), Some("dir"));
+            assert_eq!(header.enc.as_deref(), Some(enc.name()));
+            assert_eq!(jwe.iv.len(), enc.iv_len());
+
+            let bytes = DagJoseCodec::encode_to_vec(&jwe).unwrap();
+            let decoded: JsonWebEncryption = DagJoseCodec::decode_from_slice(&bytes).unwrap();
+            assert_eq!(decoded.decrypt(&key(len)).unwrap(), PLAINTEXT);
+        }
+        assert!(matches!(
+            JweBuilder::new(ContentEncryption::A128Gcm)
+                .recipient(&key(32))
+                .build(PLAINTEXT),
+            Err(Error::InvalidKey("A128GCM"))
+        ));
+    }
+
+    #[test]
+    fn build_recipients() {
+        let jwe = JweBuilder::new(ContentEncryption::XC20P)
+            .unprotected(BTreeMap::from([("typ".to_string(), Ipld::from("JWE"))]))
+            .recipient(&XorKey(1))
+            .recipient_with_header(
+                &XorKey(2),
+                BTreeMap::from([("note".to_string(), Ipld::from("second"))]),
+            )
+            .build(PLAINTEXT)
+            .unwrap();
+        assert_eq!(jwe.protected.as_slice(), br#"{"enc":"XC20P"}"#);
+        assert_eq!(jwe.recipients.len(), 2);
+        assert_eq!(
+            jwe.recipients[1].header.get("note"),
+            Some(&Ipld::from("second"))
+        );
+        assert_eq!(jwe.decrypt(&XorKey(1)).unwrap(), PLAINTEXT);
+        assert_eq!(jwe.decrypt(&XorKey(2)).unwrap(), PLAINTEXT);
+        assert!(matches!(jwe.decrypt(&XorKey(3)), Err(Error::Decryption)));
+        assert!(matches!(
+            jwe.decrypt(&key(32)),
+            Err(Error::UnsupportedAlgorithm(ref alg)) if alg == "XOR"
+        ));
+    }
+
+    #[test]
+    fn reject_invalid_recipients() {
+        assert!(matches!(
+            JweBuilder::new(ContentEncryption::XC20P).build(PLAINTEXT),
+            Err(Error::MissingRecipient)
+        ));
+        assert!(matches!(
+            JweBuilder::new(ContentEncryption::XC20P)
+                .recipient(&key(32))
+                .recipient(&XorKey(1))
+                .build(PLAINTEXT),
+            Err(Error::InvalidJwe(_))
+        ));
+        // The recipient header repeats a shared unprotected header parameter.
+        assert!(matches!(
+            JweBuilder::new(ContentEncryption::XC20P)
+                .unprotected(BTreeMap::from([("xor".to_string(), Ipld::from(1))]))
+                .recipient(&XorKey(1))
+                .build(PLAINTEXT),
+            Err(Error::DuplicateHeader(ref name)) if name == "xor"
+        ));
+        // The recipient header names a different algorithm than the encrypter.
+        let header = |alg: &str| BTreeMap::from([("alg".to_string(), Ipld::from(alg))]);
+        assert!(matches!(
+            JweBuilder::new(ContentEncryption::XC20P)
+                .recipient_with_header(&XorKey(1), header("A256KW"))
+                .build(PLAINTEXT),
+            Err(Error::DuplicateHeader(ref name)) if name == "alg"
+        ));
+        assert!(JweBuilder::new(ContentEncryption::XC20P)
+            .recipient_with_header(&XorKey(1), header("XOR"))
+            .build(PLAINTEXT)
+            .is_ok());
+    }
+
+    #[test]
+    fn ipld_roundtrip() {
+        let node = Ipld::Map(BTreeMap::from([
+            ("name".to_string(), Ipld::from("secret")),
+            ("count".to_string(), Ipld::from(0)),
+        ]));
+        let key = key(32);
+        let jwe = JweBuilder::new(ContentEncryption::XC20P)
+            .recipient(&key)
+            .build_ipld(&node)
+            .unwrap();
+        assert_eq!(jwe.decrypt_ipld(&key).unwrap(), node);
+        assert_eq!(jwe.decrypt_padded_ipld(&key).unwrap(), node);
+
+        let jwe = JweBuilder::new(ContentEncryption::XC20P)
+            .recipient(&key)
+            .build_padded_ipld(&node, JweBuilder::DEFAULT_BLOCK_SIZE)
+            .unwrap();
+        assert_eq!(
+            jwe.decrypt(&key).unwrap().len(),
+            JweBuilder::DEFAULT_BLOCK_SIZE
+        );
+        assert_eq!(jwe.decrypt_padded_ipld(&key).unwrap(), node);
+        assert!(matches!(jwe.decrypt_ipld(&key), Err(Error::CborDecode(_))));
+    }
+}
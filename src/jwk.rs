@@ -0,0 +1,322 @@
+//! JSON Web Keys as found in the `jwk` and `epk` header parameters.
+//!
+//! Only the public parameters of asymmetric keys are represented, other members such as `kid`
+//! or `use` are ignored.
+//!
+//! See https://www.rfc-editor.org/rfc/rfc7517, https://www.rfc-editor.org/rfc/rfc7518#section-6
+//! and https://www.rfc-editor.org/rfc/rfc8037#section-2
+use std::collections::BTreeMap;
+
+use ipld_core::ipld::Ipld;
+use sha2::{Digest, Sha256};
+
+use crate::{error::Error, multicodec};
+
+/// A JSON Web Key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Jwk {
+    /// An octet key pair public key, `kty` `OKP`.
+    Okp {
+        /// The curve, such as `Ed25519` or `X25519`.
+        crv: String,
+        /// The public key.
+        x: Vec<u8>,
+    },
+    /// An elliptic curve public key, `kty` `EC`.
+    Ec {
+        /// The curve, such as `P-256` or `secp256k1`.
+        crv: String,
+        /// The x coordinate.
+        x: Vec<u8>,
+        /// The y coordinate.
+        y: Vec<u8>,
+    },
+    /// A symmetric key, `kty` `oct`.
+    Oct {
+        /// The key value.
+        k: Vec<u8>,
+    },
+}
+
+impl Jwk {
+    /// The `kty` parameter of the key.
+    pub fn kty(&self) -> &'static str {
+        match self {
+            Self::Okp { .. } => "OKP",
+            Self::Ec { .. } => "EC",
+            Self::Oct { .. } => "oct",
+        }
+    }
+
+    /// The SHA-256 JWK thumbprint of the key, computed over its required members.
+    ///
+    /// See https://www.rfc-editor.org/rfc/rfc7638
+    pub fn thumbprint(&self) -> [u8; 32] {
+        let string = |value: &str| serde_json::Value::from(value).to_string();
+        // The required members in lexicographic order without whitespace.
+        let json = match self {
+            Self::Okp { crv, x } => format!(
+                r#"{{"crv":{},"kty":"OKP","x":"{}"}}"#,
+                string(crv),
+                base64_url::encode(x)
+            ),
+            Self::Ec { crv, x, y } => format!(
+                r#"{{"crv":{},"kty":"EC","x":"{}","y":"{}"}}"#,
+                string(crv),
+                base64_url::encode(x),
+                base64_url::encode(y)
+            ),
+            Self::Oct { k } => format!(r#"{{"k":"{}","kty":"oct"}}"#, base64_url::encode(k)),
+        };
+        Sha256::digest(json.as_bytes()).into()
+    }
+
+    /// Decode a multicodec prefixed public key, as used by did:key.
+    ///
+    /// Compressed elliptic curve keys are only supported when the feature of the curve is
+    /// enabled, `es256k` for secp256k1, `es256` for P-256 and `es384` for P-384.
+    pub fn from_multicodec(bytes: &[u8]) -> Result<Self, Error> {
+        let (code, public) = multicodec::split(bytes).ok_or(Error::InvalidJwk("multicodec"))?;
+        let okp = |crv: &str| {
+            if public.len() != 32 {
+                return Err(Error::InvalidKey("OKP"));
+            }
+            Ok(Self::Okp {
+                crv: crv.to_string(),
+                x: public.to_vec(),
+            })
+        };
+        match code {
+            multicodec::ED25519_PUB => okp("Ed25519"),
+            multicodec::X25519_PUB => okp("X25519"),
+            multicodec::SECP256K1_PUB => ec_from_sec1(code, "secp256k1", public),
+            multicodec::P256_PUB => ec_from_sec1(code, "P-256", public),
+            multicodec::P384_PUB => ec_from_sec1(code, "P-384", public),
+            code => Err(Error::UnsupportedMulticodec(code)),
+        }
+    }
+
+    /// Encode the public key prefixed with its multicodec, elliptic curve keys are compressed.
+    pub fn to_multicodec(&self) -> Result<Vec<u8>, Error> {
+        match self {
+            Self::Okp { crv, x } => match crv.as_str() {
+                "Ed25519" => Ok(multicodec::prefix(multicodec::ED25519_PUB, x)),
+                "X25519" => Ok(multicodec::prefix(multicodec::X25519_PUB, x)),
+                _ => Err(Error::InvalidJwk("unsupported crv")),
+            },
+            Self::Ec { crv, x, y } => {
+                let code = match crv.as_str() {
+                    "secp256k1" => multicodec::SECP256K1_PUB,
+                    "P-256" => multicodec::P256_PUB,
+                    "P-384" => multicodec::P384_PUB,
+                    _ => return Err(Error::InvalidJwk("unsupported crv")),
+                };
+                let parity = y.last().ok_or(Error::InvalidJwk("y"))? & 1;
+                let mut compressed = vec![0x02 | parity];
+                compressed.extend_from_slice(x);
+                Ok(multicodec::prefix(code, &compressed))
+            }
+            Self::Oct { .. } => Err(Error::KeyUsage {
+                key: "oct",
+                usage: "multicodec encoding",
+            }),
+        }
+    }
+}
+
+/// Split a SEC1 encoded elliptic curve public key into its coordinates, decompressing it if
+/// needed.
+fn ec_from_sec1(code: u64, crv: &'static str, public: &[u8]) -> Result<Jwk, Error> {
+    let uncompressed = match public.first() {
+        Some(0x04) => public.to_vec(),
+        Some(0x02 | 0x03) => {
+            decompress(crv, public).ok_or(Error::UnsupportedMulticodec(code))??
+        }
+        _ => return Err(Error::InvalidKey(crv)),
+    };
+    let coordinates = &uncompressed[1..];
+    if coordinates.len() % 2 != 0 {
+        return Err(Error::InvalidKey(crv));
+    }
+    let (x, y) = coordinates.split_at(coordinates.len() / 2);
+    Ok(Jwk::Ec {
+        crv: crv.to_string(),
+        x: x.to_vec(),
+        y: y.to_vec(),
+    })
+}
+
+/// Decompress a SEC1 encoded public key, `None` if the curve is not enabled.
+#[cfg_attr(
+    not(any(feature = "es256", feature = "es256k", feature = "es384")),
+    allow(unused_variables)
+)]
+fn decompress(crv: &'static str, public: &[u8]) -> Option<Result<Vec<u8>, Error>> {
+    match crv {
+        #[cfg(feature = "es256k")]
+        "secp256k1" => {
+            use k256::elliptic_curve::sec1::ToEncodedPoint;
+            Some(
+                k256::PublicKey::from_sec1_bytes(public)
+                    .map(|key| key.to_encoded_point(false).as_bytes().to_vec())
+                    .map_err(|_| Error::InvalidKey(crv)),
+            )
+        }
+        #[cfg(feature = "es256")]
+        "P-256" => {
+            use p256::elliptic_curve::sec1::ToEncodedPoint;
+            Some(
+                p256::PublicKey::from_sec1_bytes(public)
+                    .map(|key| key.to_encoded_point(false).as_bytes().to_vec())
+                    .map_err(|_| Error::InvalidKey(crv)),
+            )
+        }
+        #[cfg(feature = "es384")]
+        "P-384" => {
+            use p384::elliptic_curve::sec1::ToEncodedPoint;
+            Some(
+                p384::PublicKey::from_sec1_bytes(public)
+                    .map(|key| key.to_encoded_point(false).as_bytes().to_vec())
+                    .map_err(|_| Error::InvalidKey(crv)),
+            )
+        }
+        _ => None,
+    }
+}
+
+impl TryFrom<&Ipld> for Jwk {
+    type Error = Error;
+
+    fn try_from(value: &Ipld) -> Result<Self, Self::Error> {
+        let Ipld::Map(map) = value else {
+            return Err(Error::InvalidJwk("not a map"));
+        };
+        let string = |name: &'static str| match map.get(name) {
+            Some(Ipld::String(value)) => Ok(value.as_str()),
+            _ => Err(Error::InvalidJwk(name)),
+        };
+        let bytes = |name: &'static str| {
+            base64_url::decode(string(name)?).map_err(|_| Error::InvalidJwk(name))
+        };
+        match string("kty")? {
+            "OKP" => Ok(Self::Okp {
+                crv: string("crv")?.to_string(),
+                x: bytes("x")?,
+            }),
+            "EC" => Ok(Self::Ec {
+                crv: string("crv")?.to_string(),
+                x: bytes("x")?,
+                y: bytes("y")?,
+            }),
+            "oct" => Ok(Self::Oct { k: bytes("k")? }),
+            _ => Err(Error::InvalidJwk("unsupported kty")),
+        }
+    }
+}
+
+impl From<&Jwk> for Ipld {
+    fn from(jwk: &Jwk) -> Self {
+        let mut map = BTreeMap::from([("kty".to_string(), Ipld::from(jwk.kty()))]);
+        let mut insert = |name: &str, value: String| {
+            map.insert(name.to_string(), Ipld::String(value));
+        };
+        match jwk {
+            Jwk::Okp { crv, x } => {
+                insert("crv", crv.clone());
+                insert("x", base64_url::encode(x));
+            }
+            Jwk::Ec { crv, x, y } => {
+                insert("crv", crv.clone());
+                insert("x", base64_url::encode(x));
+                insert("y", base64_url::encode(y));
+            }
+            Jwk::Oct { k } => insert("k", base64_url::encode(k)),
+        }
+        Ipld::Map(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ipld(json: &str) -> Ipld {
+        let value: BTreeMap<String, String> = serde_json::from_str(json).unwrap();
+        Ipld::Map(
+            value
+                .into_iter()
+                .map(|(name, value)| (name, Ipld::String(value)))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn thumbprints() {
+        for (json, thumbprint) in [
+            // Example from https://www.rfc-editor.org/rfc/rfc8037#appendix-A.3
+            (
+                r#"{"kty":"OKP","crv":"Ed25519","x":"11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo"}"#,
+                "kPrK_qmxVWaYVA9wwBF6Iuo3vVzz7TxHCTwXBygrS4k",
+            ),
+            (
+                r#"{"kty":"EC","crv":"P-256","x":"f83OJ3D2xF1Bg8vub9tLe1gHMzV76e8Tus9uPHvRVEU","y":"x_FEzRu9m36HLN_tue659LNpXW6pCyStikYjKIWI5a0","kid":"ignored"}"#,
+                "oKIywvGUpTVTyxMQ3bwIIeQUudfr_CkLMjCE19ECD-U",
+            ),
+            (
+                r#"{"kty":"oct","k":"GawgguFyGrWKav7AX4VKUg"}"#,
+                "k1JnWRfC-5zzmL72vXIuBgTLfVROXBakS4OmGcrMCoc",
+            ),
+        ] {
+            let jwk = Jwk::try_from(&ipld(json)).unwrap();
+            assert_eq!(base64_url::encode(&jwk.thumbprint()), thumbprint);
+            // Members other than the key parameters are dropped.
+            let roundtrip = Jwk::try_from(&Ipld::from(&jwk)).unwrap();
+            assert_eq!(roundtrip, jwk);
+        }
+    }
+
+    #[test]
+    fn reject_invalid() {
+        assert!(matches!(
+            Jwk::try_from(&ipld(r#"{"kty":"RSA","n":"AQAB","e":"AQAB"}"#)),
+            Err(Error::InvalidJwk("unsupported kty"))
+        ));
+        assert!(matches!(
+            Jwk::try_from(&ipld(r#"{"kty":"EC","crv":"P-256","x":"AQAB"}"#)),
+            Err(Error::InvalidJwk("y"))
+        ));
+        assert!(matches!(
+            Jwk::try_from(&Ipld::from("OKP")),
+            Err(Error::InvalidJwk(_))
+        ));
+    }
+
+    #[test]
+    fn multicodec_okp() {
+        let jwk = Jwk::Okp {
+            crv: "X25519".to_string(),
+            x: vec![9; 32],
+        };
+        let bytes = jwk.to_multicodec().unwrap();
+        assert_eq!(bytes[..2], [0xec, 0x01]);
+        assert_eq!(Jwk::from_multicodec(&bytes).unwrap(), jwk);
+        assert!(matches!(
+            Jwk::from_multicodec(&bytes[..20]),
+            Err(Error::InvalidKey("OKP"))
+        ));
+        assert!(matches!(
+            Jwk::Oct { k: vec![1] }.to_multicodec(),
+            Err(Error::KeyUsage { key: "oct", .. })
+        ));
+    }
+
+    #[cfg(feature = "es256")]
+    #[test]
+    fn multicodec_ec() {
+        let jwk = Jwk::try_from(&ipld(r#"{"kty":"EC","crv":"P-256","x":"f83OJ3D2xF1Bg8vub9tLe1gHMzV76e8Tus9uPHvRVEU","y":"x_FEzRu9m36HLN_tue659LNpXW6pCyStikYjKIWI5a0"}"#)).unwrap();
+        let bytes = jwk.to_multicodec().unwrap();
+        assert_eq!(bytes[..3], [0x80, 0x24, 0x03]);
+        assert_eq!(bytes.len(), 2 + 33);
+        assert_eq!(Jwk::from_multicodec(&bytes).unwrap(), jwk);
+    }
+}
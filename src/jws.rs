@@ -0,0 +1,318 @@
+//! Signing and verification of JSON Web Signatures.
+//!
+//! Algorithms are provided by implementations of the [`Signer`] and [`Verifier`] traits.
+use std::collections::BTreeMap;
+
+use ipld_core::{cid::Cid, ipld::Ipld};
+
+use crate::{bytes::Bytes, error::Error, JsonWebSignature, ProtectedHeader, Signature};
+
+/// Produces signatures using a single JWS algorithm.
+pub trait Signer {
+    /// The `alg` header parameter value of the produced signatures.
+    fn algorithm(&self) -> &str;
+
+    /// Sign the signing input returning the raw signature bytes.
+    fn sign(&self, signing_input: &[u8]) -> Result<Vec<u8>, Error>;
+}
+
+/// Verifies signatures produced with a single JWS algorithm.
+pub trait Verifier {
+    /// The `alg` header parameter value this verifier handles.
+    fn algorithm(&self) -> &str;
+
+    /// Verify the raw signature bytes over the signing input.
+    fn verify(&self, signing_input: &[u8], signature: &[u8]) -> Result<(), Error>;
+}
+
+impl JsonWebSignature {
+    /// The signing input of a signature, `base64url(protected) || '.' || base64url(payload)`.
+    ///
+    /// See https://www.rfc-editor.org/rfc/rfc7515#section-5.1
+    pub fn signing_input(&self, signature: &Signature) -> Vec<u8> {
+        let mut input = signature
+            .protected
+            .as_ref()
+            .map(Bytes::to_base64)
+            .unwrap_or_default()
+            .into_bytes();
+        input.push(b'.');
+        input.extend_from_slice(self.payload.to_base64().as_bytes());
+        input
+    }
+
+    /// Sign the payload and append the new signature.
+    ///
+    /// The `alg` parameter of the protected header is set to the algorithm of the signer.
+    pub fn sign(
+        &mut self,
+        signer: &dyn Signer,
+        protected: ProtectedHeader,
+    ) -> Result<&Signature, Error> {
+        self.sign_with_header(signer, protected, BTreeMap::new())
+    }
+
+    /// Sign the payload and append the new signature with an unprotected header.
+    ///
+    /// The `alg` parameter of the protected header is set to the algorithm of the signer.
+    pub fn sign_with_header(
+        &mut self,
+        signer: &dyn Signer,
+        mut protected: ProtectedHeader,
+        header: BTreeMap<String, Ipld>,
+    ) -> Result<&Signature, Error> {
+        self.check_link()?;
+        protected.alg = Some(signer.algorithm().to_string());
+        let mut signature = Signature {
+            header,
+            protected: Some(protected.to_json()?),
+            signature: Bytes::default(),
+        };
+        signature.signature = signer.sign(&self.signing_input(&signature))?.into();
+        self.signatures.push(signature);
+        Ok(&self.signatures[self.signatures.len() - 1])
+    }
+
+    /// Verify each signature using the verifier for the `alg` of its protected header.
+    ///
+    /// The results are returned in the same order as `signatures`.
+    pub fn verify(&self, verifiers: &[&dyn Verifier]) -> Vec<Result<(), Error>> {
+        self.signatures
+            .iter()
+            .map(|signature| self.verify_signature(signature, verifiers))
+            .collect()
+    }
+
+    pub(crate) fn verify_signature(
+        &self,
+        signature: &Signature,
+        verifiers: &[&dyn Verifier],
+    ) -> Result<(), Error> {
+        let header = signature
+            .protected_header()?
+            .ok_or(Error::MissingAlgorithm)?;
+        // No extensions are understood, so any critical extension must be rejected.
+        if let Some(name) = header.crit.unwrap_or_default().into_iter().next() {
+            return Err(Error::UnsupportedCritical(name));
+        }
+        let alg = header.alg.ok_or(Error::MissingAlgorithm)?;
+        let verifier = verifiers
+            .iter()
+            .find(|verifier| verifier.algorithm() == alg)
+            .ok_or(Error::UnsupportedAlgorithm(alg))?;
+        verifier.verify(&self.signing_input(signature), &signature.signature)
+    }
+}
+
+/// Builds a signed [`JsonWebSignature`] over a CID payload.
+///
+/// The payload is the binary CID, so the `payload` and `link` of the built value always agree.
+///
+/// ```
+/// use dag_jose::{JwsBuilder, ProtectedHeader, Signer};
+/// use ipld_core::cid::Cid;
+///
+/// # struct Reverse;
+/// # impl Signer for Reverse {
+/// #     fn algorithm(&self) -> &str { "REV" }
+/// #     fn sign(&self, input: &[u8]) -> Result<Vec<u8>, dag_jose::Error> {
+/// #         Ok(input.iter().rev().copied().collect())
+/// #     }
+/// # }
+/// # let signer = Reverse;
+/// let link: Cid = "bafyreiejkvsvdq4smz44yuwhfymcuvqzavveoj2at3utujwqlllspsqr6q".parse().unwrap();
+/// let jws = JwsBuilder::new(link)
+///     .signer(&signer, ProtectedHeader::default())
+///     .build()
+///     .unwrap();
+/// assert_eq!(jws.link, link);
+/// assert_eq!(jws.payload.as_slice(), link.to_bytes());
+/// assert_eq!(
+///     jws.payload.to_base64(),
+///     "AXESIIlVZVHDkmZ5zFLHLhgqVhkFakcnQJ7pOibQWtcnyhH0"
+/// );
+/// ```
+pub struct JwsBuilder<'a> {
+    link: Cid,
+    signers: Vec<(&'a dyn Signer, ProtectedHeader, BTreeMap<String, Ipld>)>,
+}
+
+impl<'a> JwsBuilder<'a> {
+    /// Create a builder for a JWS with the CID as its payload.
+    pub fn new(link: Cid) -> Self {
+        Self {
+            link,
+            signers: Vec::new(),
+        }
+    }
+
+    /// Add a signature produced by the signer with the protected header.
+    ///
+    /// The `alg` parameter of the protected header is set to the algorithm of the signer.
+    pub fn signer(self, signer: &'a dyn Signer, protected: ProtectedHeader) -> Self {
+        self.signer_with_header(signer, protected, BTreeMap::new())
+    }
+
+    /// Add a signature produced by the signer with the protected and unprotected headers.
+    pub fn signer_with_header(
+        mut self,
+        signer: &'a dyn Signer,
+        protected: ProtectedHeader,
+        header: BTreeMap<String, Ipld>,
+    ) -> Self {
+        self.signers.push((signer, protected, header));
+        self
+    }
+
+    /// Sign the payload with each signer in the order they were added.
+    pub fn build(self) -> Result<JsonWebSignature, Error> {
+        if self.signers.is_empty() {
+            return Err(Error::MissingSigner);
+        }
+        let mut jws = JsonWebSignature {
+            link: self.link,
+            payload: self.link.to_bytes().into(),
+            signatures: Vec::with_capacity(self.signers.len()),
+        };
+        for (signer, protected, header) in self.signers {
+            jws.sign_with_header(signer, protected, header)?;
+        }
+        Ok(jws)
+    }
+}
+
+/// Whether an ECDSA signature is an ASN.1 DER `SEQUENCE` of two `INTEGER`s rather than the
+/// fixed length `R || S` form of `raw_len` bytes.
+///
+/// A signature of the raw length is always raw, whatever its first bytes are.
+#[cfg(any(feature = "es256k", feature = "es256", feature = "es384"))]
+pub(crate) fn is_der_signature(signature: &[u8], raw_len: usize) -> bool {
+    // The remainder after a DER `INTEGER`, signatures of the supported curves only use the short
+    // length form.
+    fn integer(bytes: &[u8]) -> Option<&[u8]> {
+        let [0x02, len, rest @ ..] = bytes else {
+            return None;
+        };
+        rest.get(*len as usize..)
+    }
+    if signature.len() == raw_len {
+        return false;
+    }
+    let [0x30, len, rest @ ..] = signature else {
+        return false;
+    };
+    rest.len() == *len as usize
+        && integer(rest)
+            .and_then(integer)
+            .is_some_and(<[u8]>::is_empty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Verifier that accepts a signature equal to the reversed signing input.
+    struct ReverseVerifier;
+
+    impl Verifier for ReverseVerifier {
+        fn algorithm(&self) -> &str {
+            "REV"
+        }
+
+        fn verify(&self, signing_input: &[u8], signature: &[u8]) -> Result<(), Error> {
+            if signing_input.iter().rev().eq(signature.iter()) {
+                Ok(())
+            } else {
+                Err(Error::InvalidSignature)
+            }
+        }
+    }
+
+    fn signature(protected: &str, payload: &Bytes, valid: bool) -> Signature {
+        let mut signature: Vec<u8> = format!("{}.{payload}", base64_url::encode(protected))
+            .bytes()
+            .rev()
+            .collect();
+        if !valid {
+            signature[0] ^= 1;
+        }
+        Signature {
+            header: BTreeMap::new(),
+            protected: Some(protected.as_bytes().into()),
+            signature: signature.into(),
+        }
+    }
+
+    #[test]
+    fn verify_signatures() {
+        let mut jws = JsonWebSignature::from_compact("eyJhbGciOiJFZERTQSJ9.AXESIIlVZVHDkmZ5zFLHLhgqVhkFakcnQJ7pOibQWtcnyhH0.-_9J5OZcl5lVuRlgI1NJEzc0FqEb6_2yVskUaQPducRQ4oe-N5ynCl57wDm4SPtm1L1bltrphpQeBOeWjVW1BQ").unwrap();
+        let payload = jws.payload.clone();
+        jws.signatures
+            .push(signature(r#"{"alg":"REV"}"#, &payload, true));
+        jws.signatures
+            .push(signature(r#"{"alg":"REV"}"#, &payload, false));
+        jws.signatures.push(signature(
+            r#"{"alg":"REV","crit":["b64"],"b64":false}"#,
+            &payload,
+            true,
+        ));
+        jws.signatures
+            .push(signature(r#"{"kid":"k"}"#, &payload, true));
+
+        let results = jws.verify(&[&ReverseVerifier]);
+        assert!(matches!(results[0], Err(Error::UnsupportedAlgorithm(ref alg)) if alg == "EdDSA"));
+        assert!(results[1].is_ok());
+        assert!(matches!(results[2], Err(Error::InvalidSignature)));
+        assert!(matches!(results[3], Err(Error::UnsupportedCritical(ref name)) if name == "b64"));
+        assert!(matches!(results[4], Err(Error::MissingAlgorithm)));
+    }
+
+    struct ReverseSigner;
+
+    impl Signer for ReverseSigner {
+        fn algorithm(&self) -> &str {
+            "REV"
+        }
+
+        fn sign(&self, signing_input: &[u8]) -> Result<Vec<u8>, Error> {
+            Ok(signing_input.iter().rev().copied().collect())
+        }
+    }
+
+    #[test]
+    fn build_signatures() {
+        let link: Cid = "bafyreiejkvsvdq4smz44yuwhfymcuvqzavveoj2at3utujwqlllspsqr6q"
+            .parse()
+            .unwrap();
+        let header = BTreeMap::from([("kid".to_string(), Ipld::String("k2".to_string()))]);
+        let jws = JwsBuilder::new(link)
+            .signer(
+                &ReverseSigner,
+                ProtectedHeader {
+                    kid: Some("k1".to_string()),
+                    ..Default::default()
+                },
+            )
+            .signer_with_header(&ReverseSigner, ProtectedHeader::default(), header.clone())
+            .build()
+            .unwrap();
+        assert_eq!(jws.link, link);
+        assert_eq!(jws.payload.as_slice(), link.to_bytes());
+        assert_eq!(jws.signatures.len(), 2);
+        assert_eq!(
+            jws.signatures[0].protected.as_deref(),
+            Some(&br#"{"alg":"REV","kid":"k1"}"#[..])
+        );
+        assert!(jws.signatures[0].header.is_empty());
+        assert_eq!(jws.signatures[1].header, header);
+        assert!(jws
+            .verify(&[&ReverseVerifier])
+            .into_iter()
+            .all(|result| result.is_ok()));
+
+        assert!(matches!(
+            JwsBuilder::new(link).build(),
+            Err(Error::MissingSigner)
+        ));
+    }
+}
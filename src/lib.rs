@@ -61,9 +61,37 @@
 )]
 #![deny(missing_docs)]
 
+#[cfg(feature = "aes-kw")]
+mod aeskw;
+mod borrowed;
 mod bytes;
+mod cbor;
+#[cfg(feature = "jwe")]
+mod cleartext;
 mod codec;
+mod compact;
+#[cfg(feature = "did-key")]
+mod didkey;
+#[cfg(feature = "x25519")]
+mod ecdh;
+#[cfg(any(feature = "es256", feature = "es384"))]
+mod ecdsa;
+#[cfg(feature = "ed25519")]
+mod eddsa;
 mod error;
+#[cfg(feature = "es256k")]
+mod es256k;
+mod header;
+mod json;
+#[cfg(feature = "jwe")]
+mod jwe;
+mod jwk;
+mod jws;
+mod multicodec;
+mod resolver;
+#[cfg(feature = "rsa")]
+mod rsassa;
+mod stream;
 
 use std::collections::BTreeMap;
 
@@ -78,12 +106,76 @@ use serde_ipld_dagcbor::codec::DagCborCodec;
 #[cfg(feature = "dag-json")]
 use serde_ipld_dagjson::codec::DagJsonCodec;
 
+pub use borrowed::{JoseRef, JweRef, JwsRef, RecipientRef, SignatureRef};
+pub use bytes::Bytes;
 use codec::Encoded;
+pub use error::Error;
+pub use header::ProtectedHeader;
+pub use jwk::Jwk;
+pub use jws::{JwsBuilder, Signer, Verifier};
+pub use resolver::{AsyncKeyResolver, KeyReference, KeyResolver, MemoryResolver};
+pub use stream::{JweReader, JweStream};
+
+#[cfg(feature = "aes-kw")]
+pub use aeskw::AesKeyWrap;
+#[cfg(feature = "did-key")]
+pub use didkey::{DidKey, DidKeyResolver};
+#[cfg(feature = "x25519")]
+pub use ecdh::{X25519Decrypter, X25519Encrypter};
+#[cfg(any(feature = "es256", feature = "es384"))]
+pub use ecdsa::{EcdsaSigner, EcdsaVerifier};
+#[cfg(feature = "ed25519")]
+pub use eddsa::{Ed25519Signer, Ed25519Verifier};
+#[cfg(feature = "es256k")]
+pub use es256k::{Es256kSigner, Es256kVerifier};
+#[cfg(feature = "jwe")]
+pub use jwe::{ContentEncryption, DirectKey, JweBuilder, KeyDecrypter, KeyEncrypter};
+#[cfg(feature = "rsa")]
+pub use rsassa::RsaVerifier;
 
 /// DAG-JOSE codec
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct DagJoseCodec;
 
+impl DagJoseCodec {
+    /// Decode a JOSE value, rejecting values the DAG-JOSE specification does not allow.
+    ///
+    /// Unlike the lenient [`Codec`] implementations, unknown top level fields, values mixing
+    /// JWS and JWE fields and JWS values with an empty `signatures` list are errors naming the
+    /// offending field.
+    pub fn decode_strict_from_slice(bytes: &[u8]) -> Result<Jose, Error> {
+        Encoded::check_strict(bytes)?;
+        let encoded: Encoded = serde_ipld_dagcbor::from_reader(bytes)?;
+        encoded.try_into()
+    }
+
+    /// Decode a JOSE value, checking that the block is its canonical DAG-CBOR encoding so that
+    /// its CID is reproducible.
+    ///
+    /// The decoded value is encoded again and the first differing byte is reported along with
+    /// the path to the field containing it in the block, such as `signatures/0/protected`.
+    /// Unknown fields, unsorted keys and integers or lengths not encoded in their shortest
+    /// form are all reported as divergences.
+    pub fn decode_canonical_from_slice(bytes: &[u8]) -> Result<Jose, Error> {
+        let encoded: Encoded = serde_ipld_dagcbor::from_reader(bytes)?;
+        let mut canonical = Vec::with_capacity(bytes.len());
+        serde_ipld_dagcbor::to_writer(&mut canonical, &encoded)?;
+        if let Some(offset) = bytes
+            .iter()
+            .zip(&canonical)
+            .position(|(byte, canonical)| byte != canonical)
+            .or((bytes.len() != canonical.len()).then(|| bytes.len().min(canonical.len())))
+        {
+            let path = cbor::path_at(bytes, offset);
+            return Err(Error::NonCanonical {
+                offset,
+                field: (!path.is_empty()).then(|| path.join("/")),
+            });
+        }
+        encoded.try_into()
+    }
+}
+
 impl Links for DagJoseCodec {
     type LinksError = error::Error;
 
@@ -136,8 +228,8 @@ impl Codec<Jose> for DagJsonCodec {
     type Error = error::Error;
 
     fn decode<R: std::io::BufRead>(reader: R) -> Result<Jose, Self::Error> {
-        let encoded: Encoded = serde_ipld_dagjson::from_reader(reader)?;
-        encoded.try_into()
+        let json: json::JsonSerialization = serde_ipld_dagjson::from_reader(reader)?;
+        json.try_into()
     }
 
     fn encode<W: std::io::Write>(writer: W, data: &Jose) -> Result<(), Self::Error> {
@@ -154,17 +246,47 @@ pub struct JsonWebSignature {
     /// CID link from the payload.
     pub link: Cid,
 
-    /// The payload base64 url encoded.
-    pub payload: String,
+    /// The payload, the binary form of `link`.
+    #[serde(serialize_with = "bytes::serialize_base64")]
+    pub payload: Bytes,
 
     /// The set of signatures.
     pub signatures: Vec<Signature>,
 }
 
+impl JsonWebSignature {
+    /// Create a JWS, checking that the payload is the binary form of `link`.
+    pub fn new(link: Cid, payload: Bytes, signatures: Vec<Signature>) -> Result<Self, Error> {
+        let jws = Self {
+            link,
+            payload,
+            signatures,
+        };
+        jws.check_link()?;
+        Ok(jws)
+    }
+
+    /// Check that the payload is the binary form of `link`.
+    ///
+    /// Encoding and signing a JWS performs this check, so a JWS is never produced for a payload
+    /// that differs from its `link`.
+    pub fn check_link(&self) -> Result<(), Error> {
+        let payload = Cid::try_from(self.payload.as_slice())?;
+        if payload == self.link {
+            Ok(())
+        } else {
+            Err(Error::LinkMismatch {
+                link: self.link.to_string(),
+                payload: payload.to_string(),
+            })
+        }
+    }
+}
+
 impl<'a> From<&'a JsonWebSignature> for Ipld {
     fn from(value: &'a JsonWebSignature) -> Self {
         ipld!({
-            "payload": value.payload.to_owned(),
+            "payload": value.payload.to_base64(),
             "signatures": value.signatures.iter().map(Ipld::from).collect::<Vec<Ipld>>(),
             "link": value.link,
         })
@@ -194,14 +316,15 @@ impl Codec<JsonWebSignature> for DagJsonCodec {
     type Error = error::Error;
 
     fn decode<R: std::io::BufRead>(reader: R) -> Result<JsonWebSignature, Self::Error> {
-        let encoded: Encoded = serde_ipld_dagjson::from_reader(reader)?;
-        encoded.try_into()
+        let json: json::JsonSerialization = serde_ipld_dagjson::from_reader(reader)?;
+        json.try_into()
     }
 
     fn encode<W: std::io::Write>(writer: W, data: &JsonWebSignature) -> Result<(), Self::Error> {
         // Here we directly encode the JsonWebSignature type without using the Encoded type.
         // This is because when encoding to DAG-JSON we do not want to encode the payload etc at
         // raw bytes but instead encode them as base64url encoded strings.
+        data.check_link()?;
         Ok(serde_ipld_dagjson::to_writer(writer, &data)?)
     }
 }
@@ -212,10 +335,12 @@ pub struct Signature {
     /// The optional unprotected header.
     #[serde(skip_serializing_if = "BTreeMap::is_empty")]
     pub header: BTreeMap<String, Ipld>,
-    /// The protected header as a JSON object base64 url encoded.
-    pub protected: Option<String>,
-    /// The web signature base64 url encoded.
-    pub signature: String,
+    /// The protected header as a JSON object.
+    #[serde(serialize_with = "bytes::serialize_base64_option")]
+    pub protected: Option<Bytes>,
+    /// The web signature.
+    #[serde(serialize_with = "bytes::serialize_base64")]
+    pub signature: Bytes,
 }
 
 impl<'a> From<&'a Signature> for Ipld {
@@ -224,10 +349,10 @@ impl<'a> From<&'a Signature> for Ipld {
         if !value.header.is_empty() {
             fields.insert("header".to_string(), value.header.to_owned().into());
         }
-        if let Some(protected) = value.protected.to_owned() {
-            fields.insert("protected".to_string(), protected.into());
+        if let Some(protected) = &value.protected {
+            fields.insert("protected".to_string(), protected.to_base64().into());
         };
-        fields.insert("signature".to_string(), value.signature.to_owned().into());
+        fields.insert("signature".to_string(), value.signature.to_base64().into());
         Ipld::Map(fields)
     }
 }
@@ -236,25 +361,32 @@ impl<'a> From<&'a Signature> for Ipld {
 #[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct JsonWebEncryption {
     /// The optional additional authenticated data.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub aad: Option<String>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "bytes::serialize_base64_option"
+    )]
+    pub aad: Option<Bytes>,
 
     /// The ciphertext value resulting from authenticated encryption of the
     /// plaintext with additional authenticated data.
-    pub ciphertext: String,
+    #[serde(serialize_with = "bytes::serialize_base64")]
+    pub ciphertext: Bytes,
 
-    /// Initialization Vector value used when encrypting the plaintext base64 url encoded.
-    pub iv: String,
+    /// Initialization Vector value used when encrypting the plaintext.
+    #[serde(serialize_with = "bytes::serialize_base64")]
+    pub iv: Bytes,
 
-    /// The protected header as a JSON object base64 url encoded.
-    pub protected: String,
+    /// The protected header as a JSON object.
+    #[serde(serialize_with = "bytes::serialize_base64")]
+    pub protected: Bytes,
 
     /// The set of recipients.
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub recipients: Vec<Recipient>,
 
     /// The authentication tag value resulting from authenticated encryption.
-    pub tag: String,
+    #[serde(serialize_with = "bytes::serialize_base64")]
+    pub tag: Bytes,
 
     /// The optional unprotected header.
     #[serde(skip_serializing_if = "BTreeMap::is_empty")]
@@ -264,12 +396,15 @@ pub struct JsonWebEncryption {
 impl<'a> From<&'a JsonWebEncryption> for Ipld {
     fn from(value: &'a JsonWebEncryption) -> Self {
         let mut fields: BTreeMap<String, Ipld> = BTreeMap::new();
-        if let Some(aad) = value.aad.to_owned() {
-            fields.insert("aad".to_string(), aad.into());
+        if let Some(aad) = &value.aad {
+            fields.insert("aad".to_string(), aad.to_base64().into());
         }
-        fields.insert("ciphertext".to_string(), value.ciphertext.to_owned().into());
-        fields.insert("iv".to_string(), value.iv.to_owned().into());
-        fields.insert("protected".to_string(), value.protected.to_owned().into());
+        fields.insert(
+            "ciphertext".to_string(),
+            value.ciphertext.to_base64().into(),
+        );
+        fields.insert("iv".to_string(), value.iv.to_base64().into());
+        fields.insert("protected".to_string(), value.protected.to_base64().into());
         if !value.recipients.is_empty() {
             fields.insert(
                 "recipients".to_string(),
@@ -282,7 +417,7 @@ impl<'a> From<&'a JsonWebEncryption> for Ipld {
             );
         }
 
-        fields.insert("tag".to_string(), value.tag.to_owned().into());
+        fields.insert("tag".to_string(), value.tag.to_base64().into());
         if !value.unprotected.is_empty() {
             fields.insert(
                 "unprotected".to_string(),
@@ -304,7 +439,7 @@ impl Codec<JsonWebEncryption> for DagJoseCodec {
     }
 
     fn encode<W: std::io::Write>(writer: W, data: &JsonWebEncryption) -> Result<(), Self::Error> {
-        let encoded: Encoded = data.try_into()?;
+        let encoded: Encoded = data.into();
         Ok(serde_ipld_dagcbor::to_writer(writer, &encoded)?)
     }
 }
@@ -316,8 +451,8 @@ impl Codec<JsonWebEncryption> for DagJsonCodec {
     type Error = error::Error;
 
     fn decode<R: std::io::BufRead>(reader: R) -> Result<JsonWebEncryption, Self::Error> {
-        let encoded: Encoded = serde_ipld_dagjson::from_reader(reader)?;
-        encoded.try_into()
+        let json: json::JsonSerialization = serde_ipld_dagjson::from_reader(reader)?;
+        json.try_into()
     }
 
     fn encode<W: std::io::Write>(writer: W, data: &JsonWebEncryption) -> Result<(), Self::Error> {
@@ -332,7 +467,8 @@ impl Codec<JsonWebEncryption> for DagJsonCodec {
 #[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct Recipient {
     /// The encrypted content encryption key value.
-    pub encrypted_key: Option<String>,
+    #[serde(serialize_with = "bytes::serialize_base64_option")]
+    pub encrypted_key: Option<Bytes>,
 
     /// The optional unprotected header.
     #[serde(skip_serializing_if = "BTreeMap::is_empty")]
@@ -342,8 +478,11 @@ pub struct Recipient {
 impl<'a> From<&'a Recipient> for Ipld {
     fn from(value: &'a Recipient) -> Self {
         let mut fields: BTreeMap<String, Ipld> = BTreeMap::new();
-        if let Some(encrypted_key) = value.encrypted_key.to_owned() {
-            fields.insert("encrypted_key".to_string(), encrypted_key.into());
+        if let Some(encrypted_key) = &value.encrypted_key {
+            fields.insert(
+                "encrypted_key".to_string(),
+                encrypted_key.to_base64().into(),
+            );
         }
         if !value.header.is_empty() {
             fields.insert("header".to_string(), value.header.to_owned().into());
@@ -374,17 +513,6 @@ mod tests {
             signature: signature.into_boxed_slice(),
         }
     }
-    fn fixture_jws_base64(
-        payload: &[u8],
-        protected: &[u8],
-        signature: &[u8],
-    ) -> (String, String, String) {
-        (
-            base64_url::encode(payload),
-            base64_url::encode(protected),
-            base64_url::encode(signature),
-        )
-    }
     struct JweFixture {
         ciphertext: Box<[u8]>,
         iv: Box<[u8]>,
@@ -403,19 +531,6 @@ mod tests {
             tag: tag.into_boxed_slice(),
         }
     }
-    fn fixture_jwe_base64(
-        ciphertext: &[u8],
-        iv: &[u8],
-        protected: &[u8],
-        tag: &[u8],
-    ) -> (String, String, String, String) {
-        (
-            base64_url::encode(ciphertext),
-            base64_url::encode(iv),
-            base64_url::encode(protected),
-            base64_url::encode(tag),
-        )
-    }
     #[test]
     fn roundtrip_jws() {
         let JwsFixture {
@@ -423,20 +538,18 @@ mod tests {
             protected,
             signature,
         } = fixture_jws();
-        let (payload_b64, protected_b64, signature_b64) =
-            fixture_jws_base64(&payload, &protected, &signature);
-        let link = Cid::try_from(base64_url::decode(&payload_b64).unwrap()).unwrap();
+        let link = Cid::try_from(&payload[..]).unwrap();
         assert_roundtrip(
             DagJoseCodec,
             &JsonWebSignature {
-                payload: payload_b64,
+                payload: payload[..].into(),
                 signatures: vec![Signature {
                     header: BTreeMap::from([
                         ("k0".to_string(), Ipld::from("v0")),
                         ("k1".to_string(), Ipld::from(1)),
                     ]),
-                    protected: Some(protected_b64),
-                    signature: signature_b64,
+                    protected: Some(protected[..].into()),
+                    signature: signature[..].into(),
                 }],
                 link,
             },
@@ -454,6 +567,35 @@ mod tests {
         );
     }
     #[test]
+    fn reject_link_mismatch() {
+        let JwsFixture { payload, .. } = fixture_jws();
+        let payload = Bytes::from(&payload[..]);
+        let link = Cid::try_from(&payload[..]).unwrap();
+        let other: Cid = "bafyreigbtj4x7ip5legnfznufuopl4sg4knzc2cof6duas4b3q2fy6swua"
+            .parse()
+            .unwrap();
+        assert!(JsonWebSignature::new(link, payload.clone(), vec![]).is_ok());
+        assert!(matches!(
+            JsonWebSignature::new(other, payload.clone(), vec![]),
+            Err(Error::LinkMismatch { link: ref l, payload: ref p })
+                if *l == other.to_string() && *p == link.to_string()
+        ));
+
+        let jws = JsonWebSignature {
+            link: other,
+            payload,
+            signatures: vec![],
+        };
+        assert!(matches!(
+            DagJoseCodec::encode_to_vec(&jws),
+            Err(Error::LinkMismatch { .. })
+        ));
+        assert!(matches!(
+            jws.to_general_json(),
+            Err(Error::LinkMismatch { .. })
+        ));
+    }
+    #[test]
     fn roundtrip_jwe() {
         let JweFixture {
             ciphertext,
@@ -461,17 +603,15 @@ mod tests {
             protected,
             tag,
         } = fixture_jwe();
-        let (ciphertext_b64, iv_b64, protected_b64, tag_b64) =
-            fixture_jwe_base64(&ciphertext, &iv, &protected, &tag);
         assert_roundtrip(
             DagJoseCodec,
             &JsonWebEncryption {
                 aad: None,
-                ciphertext: ciphertext_b64,
-                iv: iv_b64,
-                protected: protected_b64,
+                ciphertext: ciphertext[..].into(),
+                iv: iv[..].into(),
+                protected: protected[..].into(),
                 recipients: vec![],
-                tag: tag_b64,
+                tag: tag[..].into(),
                 unprotected: BTreeMap::new(),
             },
             &ipld!({
@@ -482,6 +622,171 @@ mod tests {
             }),
         );
     }
+    #[test]
+    fn decode_strict() {
+        let JwsFixture {
+            payload,
+            protected,
+            signature,
+        } = fixture_jws();
+        let JweFixture { iv, .. } = fixture_jwe();
+        let encode = |ipld: Ipld| DagJoseCodec::encode_to_vec(&ipld).unwrap();
+        let signatures = ipld!([{ "protected": protected, "signature": signature }]);
+
+        let jws = encode(ipld!({ "payload": payload.clone(), "signatures": signatures.clone() }));
+        assert_eq!(
+            DagJoseCodec::decode_strict_from_slice(&jws).unwrap(),
+            DagJoseCodec::decode_from_slice(&jws).unwrap()
+        );
+
+        let unknown = encode(ipld!({
+            "payload": payload.clone(),
+            "signatures": signatures.clone(),
+            "extra": 1,
+        }));
+        assert!(matches!(
+            DagJoseCodec::decode_strict_from_slice(&unknown),
+            Err(Error::UnknownField(ref field)) if field == "extra"
+        ));
+        // The lenient decoding ignores unknown fields.
+        assert!(<DagJoseCodec as Codec<Jose>>::decode_from_slice(&unknown).is_ok());
+
+        let mixed = encode(ipld!({
+            "payload": payload.clone(),
+            "signatures": signatures,
+            "iv": iv,
+        }));
+        assert!(matches!(
+            DagJoseCodec::decode_strict_from_slice(&mixed),
+            Err(Error::MixedFields { ref jws, ref jwe }) if jws == "payload" && jwe == "iv"
+        ));
+        assert!(matches!(
+            DagJoseCodec::decode_from_slice(&mixed),
+            Ok(Jose::Signature(_))
+        ));
+
+        let empty = encode(ipld!({ "payload": payload, "signatures": [] }));
+        assert!(matches!(
+            DagJoseCodec::decode_strict_from_slice(&empty),
+            Err(Error::EmptySignatures)
+        ));
+        // The signatures are the last field, an empty array with a longer head is still empty.
+        let mut longer = empty.clone();
+        longer.pop();
+        longer.extend([0x98, 0x00]);
+        assert!(matches!(
+            DagJoseCodec::decode_strict_from_slice(&longer),
+            Err(Error::EmptySignatures)
+        ));
+        // Indefinite lengths are not DAG-CBOR.
+        let mut indefinite = empty;
+        indefinite.pop();
+        indefinite.extend([0x9f, 0xff]);
+        assert!(matches!(
+            DagJoseCodec::decode_strict_from_slice(&indefinite),
+            Err(Error::InvalidCbor(_))
+        ));
+    }
+    #[test]
+    fn decode_canonical() {
+        let JwsFixture {
+            payload,
+            protected,
+            signature,
+        } = fixture_jws();
+        let encode = |ipld: Ipld| DagJoseCodec::encode_to_vec(&ipld).unwrap();
+        let signatures = ipld!([{
+            "header": { "k1": 1 },
+            "protected": protected,
+            "signature": signature,
+        }]);
+        let canonical =
+            encode(ipld!({ "payload": payload.clone(), "signatures": signatures.clone() }));
+        assert_eq!(
+            DagJoseCodec::decode_canonical_from_slice(&canonical).unwrap(),
+            DagJoseCodec::decode_from_slice(&canonical).unwrap()
+        );
+
+        // The integer 1 encoded in two bytes instead of one.
+        let k1 = canonical
+            .windows(4)
+            .position(|window| window == [0x62, b'k', b'1', 0x01])
+            .unwrap();
+        let mut long_int = canonical.clone();
+        long_int.splice(k1 + 3..k1 + 4, [0x18, 0x01]);
+        assert!(matches!(
+            DagJoseCodec::decode_canonical_from_slice(&long_int),
+            Err(Error::NonCanonical { offset, field: Some(ref field) })
+                if offset == k1 + 3 && field == "signatures/0/header/k1"
+        ));
+
+        // The fields in reverse order.
+        let mut unsorted = vec![0xa2];
+        for ipld in [
+            Ipld::from("signatures"),
+            signatures,
+            Ipld::from("payload"),
+            Ipld::Bytes(payload.to_vec()),
+        ] {
+            unsorted.extend(encode(ipld));
+        }
+        assert!(<DagJoseCodec as Codec<Jose>>::decode_from_slice(&unsorted).is_ok());
+        assert!(matches!(
+            DagJoseCodec::decode_canonical_from_slice(&unsorted),
+            Err(Error::NonCanonical { offset: 1, field: Some(ref field) }) if field == "signatures"
+        ));
+    }
+    #[cfg(feature = "dag-json")]
+    #[test]
+    fn roundtrip_dag_json() {
+        let JwsFixture {
+            payload,
+            protected,
+            signature,
+        } = fixture_jws();
+        let jws = JsonWebSignature {
+            payload: payload[..].into(),
+            signatures: vec![Signature {
+                header: BTreeMap::from([("k0".to_string(), Ipld::from("v0"))]),
+                protected: Some(protected[..].into()),
+                signature: signature[..].into(),
+            }],
+            link: Cid::try_from(&payload[..]).unwrap(),
+        };
+        let bytes = DagJsonCodec::encode_to_vec(&jws).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(
+            json["payload"],
+            "AXESIIlVZVHDkmZ5zFLHLhgqVhkFakcnQJ7pOibQWtcnyhH0"
+        );
+        assert_eq!(json["signatures"][0]["protected"], "eyJhbGciOiJFZERTQSJ9");
+        let decoded: JsonWebSignature = DagJsonCodec::decode_from_slice(&bytes).unwrap();
+        assert_eq!(decoded, jws);
+        let decoded: Jose = DagJsonCodec::decode_from_slice(&bytes).unwrap();
+        assert_eq!(decoded, Jose::Signature(jws));
+
+        let JweFixture {
+            ciphertext,
+            iv,
+            protected,
+            tag,
+        } = fixture_jwe();
+        let jwe = JsonWebEncryption {
+            aad: Some(b"aad"[..].into()),
+            ciphertext: ciphertext[..].into(),
+            iv: iv[..].into(),
+            protected: protected[..].into(),
+            recipients: vec![],
+            tag: tag[..].into(),
+            unprotected: BTreeMap::new(),
+        };
+        let bytes = DagJsonCodec::encode_to_vec(&jwe).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(json["iv"], "PSWIuAyO8CpevzCL");
+        assert_eq!(json["aad"], "YWFk");
+        let decoded: JsonWebEncryption = DagJsonCodec::decode_from_slice(&bytes).unwrap();
+        assert_eq!(decoded, jwe);
+    }
 
     // Utility for testing codecs.
     //
@@ -0,0 +1,46 @@
+//! Multicodec prefixes of public keys.
+//!
+//! See https://github.com/multiformats/multicodec/blob/master/table.csv
+pub(crate) const SECP256K1_PUB: u64 = 0xe7;
+pub(crate) const X25519_PUB: u64 = 0xec;
+pub(crate) const ED25519_PUB: u64 = 0xed;
+pub(crate) const P256_PUB: u64 = 0x1200;
+pub(crate) const P384_PUB: u64 = 0x1201;
+
+/// Split the unsigned varint multicodec prefix from the bytes.
+pub(crate) fn split(bytes: &[u8]) -> Option<(u64, &[u8])> {
+    let mut code = 0;
+    // Codes are at most 9 bytes long.
+    for (i, &b) in bytes.iter().enumerate().take(9) {
+        code |= u64::from(b & 0x7f) << (7 * i);
+        if b & 0x80 == 0 {
+            return Some((code, &bytes[i + 1..]));
+        }
+    }
+    None
+}
+
+/// Prefix the bytes with the unsigned varint multicodec code.
+pub(crate) fn prefix(mut code: u64, bytes: &[u8]) -> Vec<u8> {
+    let mut prefixed = Vec::with_capacity(2 + bytes.len());
+    while code >= 0x80 {
+        prefixed.push(code as u8 | 0x80);
+        code >>= 7;
+    }
+    prefixed.push(code as u8);
+    prefixed.extend_from_slice(bytes);
+    prefixed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn varint() {
+        assert_eq!(prefix(ED25519_PUB, &[1]), [0xed, 0x01, 1]);
+        assert_eq!(prefix(P256_PUB, &[1]), [0x80, 0x24, 1]);
+        assert_eq!(split(&[0x80, 0x24, 1]), Some((P256_PUB, &[1][..])));
+        assert_eq!(split(&[0x80]), None);
+    }
+}
@@ -0,0 +1,301 @@
+//! Resolution of signature verification keys from JWS header parameters.
+//!
+//! Integrators implement [`KeyResolver`] or [`AsyncKeyResolver`] to look up keys by the `kid`
+//! and `jwk` parameters of each signature, for example from DID documents.
+use std::{collections::HashMap, future::Future, sync::Arc};
+
+use ipld_core::ipld::Ipld;
+
+use crate::{error::Error, JsonWebSignature, Jwk, Signature, Verifier};
+
+/// The header parameters of a signature identifying its verification key.
+///
+/// Parameters of the protected header take precedence over the unprotected header.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyReference {
+    /// The `alg` parameter of the protected header.
+    pub alg: String,
+    /// The `kid` parameter.
+    pub kid: Option<String>,
+    /// The `jwk` parameter, when it is a key type supported by [`Jwk`].
+    ///
+    /// Other keys, such as RSA keys, are left out so the signature can still be resolved by
+    /// its `kid`.
+    pub jwk: Option<Jwk>,
+}
+
+impl KeyReference {
+    /// Read the key parameters of a signature.
+    pub fn from_signature(signature: &Signature) -> Result<Self, Error> {
+        let protected = signature.protected_header()?.unwrap_or_default();
+        let alg = protected.alg.ok_or(Error::MissingAlgorithm)?;
+        let kid = match protected.kid {
+            Some(kid) => Some(kid),
+            None => match signature.header.get("kid") {
+                Some(Ipld::String(kid)) => Some(kid.clone()),
+                Some(_) => return Err(Error::InvalidHeader("kid")),
+                None => None,
+            },
+        };
+        let jwk = protected
+            .additional
+            .get("jwk")
+            .or_else(|| signature.header.get("jwk"))
+            .and_then(|jwk| Jwk::try_from(jwk).ok());
+        Ok(Self { alg, kid, jwk })
+    }
+}
+
+/// Resolves the verification key of a signature.
+pub trait KeyResolver {
+    /// Resolve a verifier for the referenced key.
+    fn resolve(&self, key: &KeyReference) -> Result<Arc<dyn Verifier + Send + Sync>, Error>;
+}
+
+/// Resolves the verification key of a signature asynchronously, for resolution requiring
+/// network access.
+///
+/// Every [`KeyResolver`] is also an asynchronous resolver through a blanket implementation, so
+/// a type implementing [`KeyResolver`] cannot implement this trait itself. Resolvers that need
+/// to await should only implement this trait.
+pub trait AsyncKeyResolver {
+    /// Resolve a verifier for the referenced key.
+    fn resolve(
+        &self,
+        key: &KeyReference,
+    ) -> impl Future<Output = Result<Arc<dyn Verifier + Send + Sync>, Error>> + Send;
+}
+
+impl<R: KeyResolver + Sync> AsyncKeyResolver for R {
+    fn resolve(
+        &self,
+        key: &KeyReference,
+    ) -> impl Future<Output = Result<Arc<dyn Verifier + Send + Sync>, Error>> + Send {
+        std::future::ready(KeyResolver::resolve(self, key))
+    }
+}
+
+/// Resolves keys by `kid` from a fixed set of verifiers.
+#[derive(Clone, Default)]
+pub struct MemoryResolver {
+    keys: HashMap<String, Arc<dyn Verifier + Send + Sync>>,
+}
+
+impl MemoryResolver {
+    /// Create a resolver without keys.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the verifier of a key id.
+    pub fn with_key(mut self, kid: &str, verifier: impl Verifier + Send + Sync + 'static) -> Self {
+        self.keys.insert(kid.to_string(), Arc::new(verifier));
+        self
+    }
+}
+
+impl KeyResolver for MemoryResolver {
+    fn resolve(&self, key: &KeyReference) -> Result<Arc<dyn Verifier + Send + Sync>, Error> {
+        let kid = key.kid.as_deref().ok_or(Error::MissingHeader("kid"))?;
+        self.keys
+            .get(kid)
+            .cloned()
+            .ok_or_else(|| Error::UnknownKey(kid.to_string()))
+    }
+}
+
+impl JsonWebSignature {
+    /// Verify each signature using the key resolved from its header parameters.
+    ///
+    /// The results are returned in the same order as `signatures`.
+    pub fn verify_with_resolver(&self, resolver: &dyn KeyResolver) -> Vec<Result<(), Error>> {
+        self.signatures
+            .iter()
+            .map(|signature| {
+                let verifier = resolver.resolve(&KeyReference::from_signature(signature)?)?;
+                self.verify_signature(signature, &[verifier.as_ref()])
+            })
+            .collect()
+    }
+
+    /// Verify each signature using the key resolved asynchronously from its header parameters.
+    ///
+    /// Keys are resolved one signature at a time, the results are returned in the same order
+    /// as `signatures`.
+    pub async fn verify_with_async_resolver(
+        &self,
+        resolver: &impl AsyncKeyResolver,
+    ) -> Vec<Result<(), Error>> {
+        let mut results = Vec::with_capacity(self.signatures.len());
+        for signature in &self.signatures {
+            let result = match KeyReference::from_signature(signature) {
+                Ok(key) => match resolver.resolve(&key).await {
+                    Ok(verifier) => self.verify_signature(signature, &[verifier.as_ref()]),
+                    Err(err) => Err(err),
+                },
+                Err(err) => Err(err),
+            };
+            results.push(result);
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeMap,
+        pin::pin,
+        task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+    };
+
+    use super::*;
+    use crate::{JwsBuilder, ProtectedHeader, Signer};
+
+    struct Reverse;
+
+    impl Signer for Reverse {
+        fn algorithm(&self) -> &str {
+            "REV"
+        }
+
+        fn sign(&self, signing_input: &[u8]) -> Result<Vec<u8>, Error> {
+            Ok(signing_input.iter().rev().copied().collect())
+        }
+    }
+
+    impl Verifier for Reverse {
+        fn algorithm(&self) -> &str {
+            "REV"
+        }
+
+        fn verify(&self, signing_input: &[u8], signature: &[u8]) -> Result<(), Error> {
+            if signing_input.iter().rev().eq(signature.iter()) {
+                Ok(())
+            } else {
+                Err(Error::InvalidSignature)
+            }
+        }
+    }
+
+    // Resolver that only resolves keys embedded as a symmetric `jwk`, after an asynchronous
+    // lookup.
+    struct JwkResolver;
+
+    impl AsyncKeyResolver for JwkResolver {
+        async fn resolve(
+            &self,
+            key: &KeyReference,
+        ) -> Result<Arc<dyn Verifier + Send + Sync>, Error> {
+            std::future::ready(()).await;
+            match &key.jwk {
+                Some(Jwk::Oct { .. }) => Ok(Arc::new(Reverse)),
+                _ => Err(Error::MissingHeader("jwk")),
+            }
+        }
+    }
+
+    fn jws() -> JsonWebSignature {
+        let header = |kid: &str| ProtectedHeader {
+            kid: Some(kid.to_string()),
+            ..Default::default()
+        };
+        JwsBuilder::new(
+            "bafyreiejkvsvdq4smz44yuwhfymcuvqzavveoj2at3utujwqlllspsqr6q"
+                .parse()
+                .unwrap(),
+        )
+        .signer(&Reverse, header("known"))
+        .signer(&Reverse, header("unknown"))
+        .signer_with_header(
+            &Reverse,
+            ProtectedHeader::default(),
+            BTreeMap::from([
+                ("kid".to_string(), Ipld::from("known")),
+                (
+                    "jwk".to_string(),
+                    Ipld::from(&Jwk::Oct { k: vec![1, 2, 3] }),
+                ),
+            ]),
+        )
+        .build()
+        .unwrap()
+    }
+
+    // Poll a future that is ready without waiting, with a waker that does nothing.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        const VTABLE: RawWakerVTable = RawWakerVTable::new(
+            |_| RawWaker::new(std::ptr::null(), &VTABLE),
+            |_| {},
+            |_| {},
+            |_| {},
+        );
+        // SAFETY: The vtable functions ignore the data pointer.
+        let waker = unsafe { Waker::from_raw(RawWaker::new(std::ptr::null(), &VTABLE)) };
+        match pin!(future).poll(&mut Context::from_waker(&waker)) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("future is not ready"),
+        }
+    }
+
+    #[test]
+    fn resolve_from_memory() {
+        let resolver = MemoryResolver::new().with_key("known", Reverse);
+        let results = jws().verify_with_resolver(&resolver);
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(Error::UnknownKey(ref kid)) if kid == "unknown"));
+        // The key id of the unprotected header is used.
+        assert!(results[2].is_ok());
+
+        let results = block_on(jws().verify_with_async_resolver(&resolver));
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+    }
+
+    #[test]
+    fn resolve_with_unsupported_jwk() {
+        let rsa = Ipld::Map(BTreeMap::from([
+            ("kty".to_string(), Ipld::from("RSA")),
+            ("n".to_string(), Ipld::from("AQAB")),
+            ("e".to_string(), Ipld::from("AQAB")),
+        ]));
+        let jws = JwsBuilder::new(
+            "bafyreiejkvsvdq4smz44yuwhfymcuvqzavveoj2at3utujwqlllspsqr6q"
+                .parse()
+                .unwrap(),
+        )
+        .signer_with_header(
+            &Reverse,
+            ProtectedHeader::default(),
+            BTreeMap::from([
+                ("kid".to_string(), Ipld::from("known")),
+                ("jwk".to_string(), rsa),
+            ]),
+        )
+        .build()
+        .unwrap();
+        assert_eq!(
+            KeyReference::from_signature(&jws.signatures[0])
+                .unwrap()
+                .jwk,
+            None
+        );
+        let resolver = MemoryResolver::new().with_key("known", Reverse);
+        assert!(jws.verify_with_resolver(&resolver)[0].is_ok());
+    }
+
+    #[test]
+    fn resolve_async() {
+        let results = block_on(jws().verify_with_async_resolver(&JwkResolver));
+        assert!(matches!(results[0], Err(Error::MissingHeader("jwk"))));
+        assert!(results[2].is_ok());
+        assert_eq!(
+            KeyReference::from_signature(&jws().signatures[1]).unwrap(),
+            KeyReference {
+                alg: "REV".to_string(),
+                kid: Some("unknown".to_string()),
+                jwk: None,
+            }
+        );
+    }
+}
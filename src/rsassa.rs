@@ -0,0 +1,205 @@
+//! RS256, RS384, RS512 and PS256 JSON Web Signatures using RSA public keys.
+//!
+//! Only verification is supported. Keys with a modulus smaller than a configurable minimum are
+//! refused.
+//!
+//! See https://www.rfc-editor.org/rfc/rfc7518#section-3.3 and
+//! https://www.rfc-editor.org/rfc/rfc7518#section-3.5
+use rsa::{traits::PublicKeyParts, BigUint, Pkcs1v15Sign, Pss, RsaPublicKey};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+use crate::{error::Error, Verifier};
+
+/// An RSA signature algorithm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Algorithm {
+    Rs256,
+    Rs384,
+    Rs512,
+    Ps256,
+}
+
+impl Algorithm {
+    fn from_name(alg: &str) -> Result<Self, Error> {
+        match alg {
+            "RS256" => Ok(Self::Rs256),
+            "RS384" => Ok(Self::Rs384),
+            "RS512" => Ok(Self::Rs512),
+            "PS256" => Ok(Self::Ps256),
+            alg => Err(Error::UnsupportedAlgorithm(alg.to_string())),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Rs256 => "RS256",
+            Self::Rs384 => "RS384",
+            Self::Rs512 => "RS512",
+            Self::Ps256 => "PS256",
+        }
+    }
+}
+
+/// Verifies JWS values with an RSA public key.
+pub struct RsaVerifier {
+    alg: Algorithm,
+    key: RsaPublicKey,
+}
+
+impl RsaVerifier {
+    /// The default minimum modulus size in bits, as required by RFC 7518.
+    pub const DEFAULT_MIN_MODULUS_BITS: usize = 2048;
+
+    /// Create a verifier for the algorithm from the big endian modulus and public exponent,
+    /// the `n` and `e` JWK parameters.
+    ///
+    /// Keys whose modulus is shorter than [`Self::DEFAULT_MIN_MODULUS_BITS`] are refused.
+    pub fn from_components(alg: &str, n: &[u8], e: &[u8]) -> Result<Self, Error> {
+        Self::from_components_with_min_bits(alg, n, e, Self::DEFAULT_MIN_MODULUS_BITS)
+    }
+
+    /// Create a verifier refusing keys whose modulus is shorter than `min_modulus_bits`.
+    pub fn from_components_with_min_bits(
+        alg: &str,
+        n: &[u8],
+        e: &[u8],
+        min_modulus_bits: usize,
+    ) -> Result<Self, Error> {
+        let alg = Algorithm::from_name(alg)?;
+        let key = RsaPublicKey::new(BigUint::from_bytes_be(n), BigUint::from_bytes_be(e))
+            .map_err(|_| Error::InvalidKey("RSA"))?;
+        let bits = key.n().bits();
+        if bits < min_modulus_bits {
+            return Err(Error::WeakRsaKey {
+                bits,
+                min: min_modulus_bits,
+            });
+        }
+        Ok(Self { alg, key })
+    }
+}
+
+impl Verifier for RsaVerifier {
+    fn algorithm(&self) -> &str {
+        self.alg.name()
+    }
+
+    fn verify(&self, signing_input: &[u8], signature: &[u8]) -> Result<(), Error> {
+        match self.alg {
+            Algorithm::Rs256 => self.key.verify(
+                Pkcs1v15Sign::new::<Sha256>(),
+                &Sha256::digest(signing_input),
+                signature,
+            ),
+            Algorithm::Rs384 => self.key.verify(
+                Pkcs1v15Sign::new::<Sha384>(),
+                &Sha384::digest(signing_input),
+                signature,
+            ),
+            Algorithm::Rs512 => self.key.verify(
+                Pkcs1v15Sign::new::<Sha512>(),
+                &Sha512::digest(signing_input),
+                signature,
+            ),
+            // The salt length must equal the digest length.
+            Algorithm::Ps256 => self.key.verify(
+                Pss::new::<Sha256>(),
+                &Sha256::digest(signing_input),
+                signature,
+            ),
+        }
+        .map_err(|_| Error::InvalidSignature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2048 bit key and signatures generated with openssl.
+    const N: &str = "vmfsiaQ89g0uUu9z6wfNn57QUIEVLsBbJs2ije7lxCG5RLzVRCtCqgsbcAQC4f18XWJnUdq4iFX4IymZpr84jj0cIqGHLPiRBpsw-1Z3bIw-SoyAhu2X-mJjIe_25pfGhZUXJXB0ylgdnFeQBeAlxVVL6F-J_7t02-JO-xY1Ft1267GhYjEGNgXv8av6CJqH_Pe-alBMwD7iVA6bIVFpnvq0eVc28fqBJAWdxr8fI1QBJZdzn17K24hvh-4ewX23iKaBSTDnYqRNcaYjmiRx7gJ9l57sqY0u7RORztwRwFhj2VEwydV-wKPxEc7cCB81bYuIjkwuEHvAkD38FVpNBQ";
+    const E: &str = "AQAB";
+    const RS256_SIGNATURE: &str = "uohvlkqadi0FRyWQ-bWdT4aXIXnl5ialgroo9l85IKIPTQ37JzpY2xRHffv_KUMK3tmN4UarkKkW10kQNbSojrCN3t2YREmtQ24_CiLIwhDkG0IDic56t0PrB2WmWl9Lqm0v_M8NUcTR-JsjOWUQwRDvmwcc47snbdsacu-eGMAoYePuSGshfO-pRf1J19pHEeiutNbuNEfSEY3CPz85aZlp4AONKhWeDdfROH3fivrxSYQRiTeDodZfoEi_nLmL4Dh0Y0AHAS2NFdiqMm5JoGaB9dGEzhtG3qm2n6ZSEivWxHJyCf2nZLdraK6ZxGtLO1kiLyAOEa2L4taYRuWRtg";
+    const PS256_SIGNATURE: &str = "vEUppIl_fXCuaAZxaa-ToyuUy12vl_locxLQp0bmKzXJrNENY7KgdCFoSBnCjYGzYK-f-POD3ULJYtRgYR5OWL0Ul3pKpecdzh85yn0KZKUmQoG4XC5IiCpplfyCf014T2M3uN2ZO6GRIAzoeYhxCacsQiP9MBebs0WCvvmTJa3fHjGDyKW21Hf3SLUozkx6yoDWPyNZ6URVM1A2w-BbCPamRgh8VtvC8pZmeFyHg967daqaN-9oA91bsaX_We8RHcXAsbztG_gJiSFhVVyYbZYO4WP6njK-nT9sT7Hd96-9sd0VIBK96SLq_OWMB_Obsue79aEkelM4oaYRBo0u_Q";
+    // 1024 bit key.
+    const WEAK_N: &str = "xmUJGmBK6WEwAin7dKYSv3-iFhJ0uS8XMDlM7xnr0ymv8Vl34nRYvZH-gyE7aWmKtj0eYqUQ_qGtFN9kZEaU_4b4Pxt5cSD9qnmBmCo6YJGBdkPv_NS5yCNQbxcrQEQ5mQcW8vhfWVWWHZDxKjSHaCoeqfnXRxQjFg2jk5xK_rM";
+
+    fn verifier(alg: &str) -> RsaVerifier {
+        RsaVerifier::from_components(
+            alg,
+            &base64_url::decode(N).unwrap(),
+            &base64_url::decode(E).unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn verify_rs256() {
+        let verifier = verifier("RS256");
+        let mut signature = base64_url::decode(RS256_SIGNATURE).unwrap();
+        assert!(verifier
+            .verify(b"eyJhbGciOiJSUzI1NiJ9.e30", &signature)
+            .is_ok());
+        signature[0] ^= 1;
+        assert!(matches!(
+            verifier.verify(b"eyJhbGciOiJSUzI1NiJ9.e30", &signature),
+            Err(Error::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn verify_ps256() {
+        let verifier = verifier("PS256");
+        let signature = base64_url::decode(PS256_SIGNATURE).unwrap();
+        assert!(verifier
+            .verify(b"eyJhbGciOiJQUzI1NiJ9.e30", &signature)
+            .is_ok());
+        assert!(matches!(
+            verifier.verify(b"eyJhbGciOiJQUzI1NiJ9.e31", &signature),
+            Err(Error::InvalidSignature)
+        ));
+        // A PKCS #1 v1.5 signature is not accepted for PS256.
+        assert!(matches!(
+            verifier.verify(
+                b"eyJhbGciOiJSUzI1NiJ9.e30",
+                &base64_url::decode(RS256_SIGNATURE).unwrap()
+            ),
+            Err(Error::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn reject_weak_key() {
+        let n = base64_url::decode(WEAK_N).unwrap();
+        let e = base64_url::decode(E).unwrap();
+        assert!(matches!(
+            RsaVerifier::from_components("RS256", &n, &e),
+            Err(Error::WeakRsaKey {
+                bits: 1024,
+                min: 2048
+            })
+        ));
+        assert!(RsaVerifier::from_components_with_min_bits("RS256", &n, &e, 1024).is_ok());
+        assert!(matches!(
+            RsaVerifier::from_components_with_min_bits(
+                "RS256",
+                &base64_url::decode(N).unwrap(),
+                &e,
+                3072
+            ),
+            Err(Error::WeakRsaKey { bits: 2048, .. })
+        ));
+    }
+
+    #[test]
+    fn reject_unknown_algorithm() {
+        assert!(matches!(
+            RsaVerifier::from_components(
+                "PS512",
+                &base64_url::decode(N).unwrap(),
+                &base64_url::decode(E).unwrap()
+            ),
+            Err(Error::UnsupportedAlgorithm(ref alg)) if alg == "PS512"
+        ));
+    }
+}
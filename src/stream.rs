@@ -0,0 +1,441 @@
+//! Streaming the ciphertext of large JWE blocks.
+//!
+//! The ciphertext makes up almost all of an encrypted block. [`JweReader`] decodes the other
+//! fields of a block and reads the ciphertext on demand from the underlying reader, while
+//! [`DagJoseCodec::encode_jwe_from_reader`] writes a block copying the ciphertext from a
+//! reader, so neither holds more than one copy of the ciphertext.
+//!
+//! DAG-CBOR sorts map keys by length, so the `recipients` and `unprotected` fields follow the
+//! ciphertext in a block. Reading them before the ciphertext, as decryption needs, requires
+//! seeking past it. [`JweStream`] reads a block in order from any reader instead, yielding the
+//! ciphertext before the fields following it.
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use ipld_core::codec::Codec;
+
+use crate::{bytes::Bytes, cbor, error::Error, DagJoseCodec, JsonWebEncryption};
+
+/// The map key of the ciphertext field, a text string of length 10.
+const CIPHERTEXT_KEY: &[u8] = b"\x6aciphertext";
+
+/// The fields of a JWE block up to its ciphertext.
+struct Head {
+    /// The block read so far, with the ciphertext replaced by an empty byte string.
+    block: Vec<u8>,
+    /// The number of map entries following the ciphertext.
+    remaining: u64,
+    /// The length of the ciphertext.
+    len: u64,
+}
+
+impl Head {
+    /// Read a block up to the start of the ciphertext.
+    fn read(reader: &mut impl Read) -> Result<Self, Error> {
+        let mut block = Vec::new();
+        let (major, fields) = cbor::read_head(reader, &mut block)?;
+        if major != 5 {
+            return Err(Error::NotJwe);
+        }
+        for field in 0..fields {
+            let key = block.len();
+            cbor::read_item(reader, &mut block)?;
+            if block[key..] != *CIPHERTEXT_KEY {
+                cbor::read_item(reader, &mut block)?;
+                continue;
+            }
+            let value = block.len();
+            let (2, len) = cbor::read_head(reader, &mut block)? else {
+                return Err(Error::InvalidJwe("ciphertext is not bytes"));
+            };
+            block.truncate(value);
+            cbor::write_head(2, 0, &mut block);
+            return Ok(Self {
+                block,
+                remaining: fields - field - 1,
+                len,
+            });
+        }
+        Err(Error::NotJwe)
+    }
+
+    /// Read the fields following the ciphertext and decode the JWE without it.
+    fn finish(mut self, reader: &mut impl Read) -> Result<JsonWebEncryption, Error> {
+        for _ in 0..self.remaining {
+            cbor::read_item(reader, &mut self.block)?;
+            cbor::read_item(reader, &mut self.block)?;
+        }
+        <DagJoseCodec as Codec<JsonWebEncryption>>::decode_from_slice(&self.block)
+    }
+}
+
+/// A JWE decoded from a DAG-JOSE block whose ciphertext is left in the reader.
+///
+/// Only the block is read, so the reader may be positioned within a larger file. The reader
+/// must support seeking to skip over the ciphertext to the fields following it, use
+/// [`JweStream`] for readers that cannot seek.
+///
+/// ```
+/// use std::io::{Cursor, Read};
+///
+/// use dag_jose::{Bytes, DagJoseCodec, JsonWebEncryption, JweReader};
+/// use ipld_core::codec::Codec;
+///
+/// # let jwe = JsonWebEncryption {
+/// #     aad: None,
+/// #     ciphertext: Bytes::from(vec![1; 1024]),
+/// #     iv: Bytes::from([2; 12]),
+/// #     protected: Bytes::from(br#"{"alg":"dir","enc":"A128GCM"}"#.to_vec()),
+/// #     recipients: vec![],
+/// #     tag: Bytes::from([3; 16]),
+/// #     unprotected: Default::default(),
+/// # };
+/// let block = DagJoseCodec::encode_to_vec(&jwe).unwrap();
+/// let mut reader = JweReader::new(Cursor::new(block)).unwrap();
+/// assert_eq!(reader.jwe().protected_header().unwrap().enc.as_deref(), Some("A128GCM"));
+/// assert_eq!(reader.ciphertext_len(), 1024);
+///
+/// let mut ciphertext = Vec::new();
+/// reader.ciphertext().unwrap().read_to_end(&mut ciphertext).unwrap();
+/// assert_eq!(ciphertext, jwe.ciphertext.as_slice());
+/// ```
+pub struct JweReader<R> {
+    reader: R,
+    jwe: JsonWebEncryption,
+    offset: u64,
+    len: u64,
+}
+
+impl<R: Read + Seek> JweReader<R> {
+    /// Decode the fields of a JWE block other than the ciphertext, skipping over it.
+    pub fn new(mut reader: R) -> Result<Self, Error> {
+        let head = Head::read(&mut reader)?;
+        let len = head.len;
+        let offset = reader.stream_position()?;
+        reader.seek(SeekFrom::Current(
+            i64::try_from(len).map_err(|_| Error::InvalidCbor("byte string length"))?,
+        ))?;
+        let jwe = head.finish(&mut reader)?;
+        Ok(Self {
+            reader,
+            jwe,
+            offset,
+            len,
+        })
+    }
+
+    /// The JWE without its ciphertext, the `ciphertext` field is empty.
+    pub fn jwe(&self) -> &JsonWebEncryption {
+        &self.jwe
+    }
+
+    /// The length of the ciphertext in bytes.
+    pub fn ciphertext_len(&self) -> u64 {
+        self.len
+    }
+
+    /// Read the ciphertext from the underlying reader.
+    ///
+    /// Each call reads the ciphertext from its start.
+    pub fn ciphertext(&mut self) -> Result<io::Take<&mut R>, Error> {
+        self.reader.seek(SeekFrom::Start(self.offset))?;
+        Ok((&mut self.reader).take(self.len))
+    }
+
+    /// Read the ciphertext into the JWE.
+    pub fn into_jwe(mut self) -> Result<JsonWebEncryption, Error> {
+        let ciphertext = self.read_ciphertext()?;
+        Ok(JsonWebEncryption {
+            ciphertext: ciphertext.into(),
+            ..self.jwe
+        })
+    }
+
+    /// Decrypt the ciphertext, reading it into a buffer which is decrypted in place.
+    #[cfg(feature = "jwe")]
+    pub fn decrypt(&mut self, decrypter: &dyn crate::KeyDecrypter) -> Result<Vec<u8>, Error> {
+        let mut buffer = self.read_ciphertext()?;
+        self.jwe.decrypt_in_place(decrypter, &mut buffer)?;
+        Ok(buffer)
+    }
+
+    fn read_ciphertext(&mut self) -> Result<Vec<u8>, Error> {
+        let len = self.len;
+        let mut buffer = Vec::new();
+        // An impossibly large length fails to reserve rather than aborting, a truncated block
+        // fails below.
+        buffer
+            .try_reserve_exact(usize::try_from(len).unwrap_or(usize::MAX))
+            .map_err(|_| Error::InvalidCbor("byte string length"))?;
+        if self.ciphertext()?.read_to_end(&mut buffer)? as u64 != len {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        Ok(buffer)
+    }
+}
+
+/// A JWE read in order from a DAG-JOSE block, yielding the ciphertext once.
+///
+/// The fields preceding the ciphertext are read when the stream is created, the ciphertext
+/// is then read through the [`Read`] implementation and [`JweStream::finish`] reads the
+/// remaining fields. Unlike [`JweReader`] the reader does not need to seek, so the block may
+/// come from a socket or pipe, but the `recipients` and `unprotected` fields are only known
+/// after the ciphertext.
+///
+/// ```
+/// use std::io::Read;
+///
+/// use dag_jose::{Bytes, DagJoseCodec, JsonWebEncryption, JweStream};
+/// use ipld_core::codec::Codec;
+///
+/// # let jwe = JsonWebEncryption {
+/// #     aad: None,
+/// #     ciphertext: Bytes::from(vec![1; 1024]),
+/// #     iv: Bytes::from([2; 12]),
+/// #     protected: Bytes::from(br#"{"alg":"dir","enc":"A128GCM"}"#.to_vec()),
+/// #     recipients: vec![],
+/// #     tag: Bytes::from([3; 16]),
+/// #     unprotected: Default::default(),
+/// # };
+/// let block = DagJoseCodec::encode_to_vec(&jwe).unwrap();
+/// let mut stream = JweStream::new(block.as_slice()).unwrap();
+/// assert_eq!(stream.ciphertext_len(), 1024);
+///
+/// let mut ciphertext = Vec::new();
+/// stream.read_to_end(&mut ciphertext).unwrap();
+/// assert_eq!(ciphertext, jwe.ciphertext.as_slice());
+/// assert_eq!(stream.finish().unwrap().iv, jwe.iv);
+/// ```
+pub struct JweStream<R> {
+    reader: R,
+    head: Head,
+    /// The number of ciphertext bytes not read yet.
+    unread: u64,
+}
+
+impl<R: Read> JweStream<R> {
+    /// Read the fields of a JWE block preceding the ciphertext.
+    pub fn new(mut reader: R) -> Result<Self, Error> {
+        let head = Head::read(&mut reader)?;
+        Ok(Self {
+            reader,
+            unread: head.len,
+            head,
+        })
+    }
+
+    /// The length of the ciphertext in bytes.
+    pub fn ciphertext_len(&self) -> u64 {
+        self.head.len
+    }
+
+    /// Skip any unread ciphertext and decode the JWE from the remaining fields, the
+    /// `ciphertext` field is empty.
+    pub fn finish(mut self) -> Result<JsonWebEncryption, Error> {
+        let unread = self.unread;
+        if io::copy(&mut self, &mut io::sink())? != unread {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        self.head.finish(&mut self.reader)
+    }
+}
+
+impl<R: Read> Read for JweStream<R> {
+    /// Read the ciphertext.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf
+            .len()
+            .min(usize::try_from(self.unread).unwrap_or(usize::MAX));
+        let read = self.reader.read(&mut buf[..len])?;
+        self.unread -= read as u64;
+        Ok(read)
+    }
+}
+
+impl DagJoseCodec {
+    /// Encode a JWE block, copying the ciphertext of `len` bytes from the reader instead of
+    /// using the `ciphertext` field of `jwe`.
+    ///
+    /// The block is identical to encoding the JWE with that ciphertext.
+    pub fn encode_jwe_from_reader<W: Write, R: Read>(
+        mut writer: W,
+        jwe: &JsonWebEncryption,
+        ciphertext: R,
+        len: u64,
+    ) -> Result<(), Error> {
+        let fields = JsonWebEncryption {
+            aad: jwe.aad.clone(),
+            ciphertext: Bytes::default(),
+            iv: jwe.iv.clone(),
+            protected: jwe.protected.clone(),
+            recipients: jwe.recipients.clone(),
+            tag: jwe.tag.clone(),
+            unprotected: jwe.unprotected.clone(),
+        };
+        let block = DagJoseCodec::encode_to_vec(&fields)?;
+        let value = cbor::map_value(&block, "ciphertext").expect("a JWE has a ciphertext");
+        let mut head = Vec::new();
+        cbor::write_head(2, len, &mut head);
+        writer.write_all(&block[..value.start])?;
+        writer.write_all(&head)?;
+        if io::copy(&mut ciphertext.take(len), &mut writer)? != len {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        writer.write_all(&block[value.end..])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, io::Cursor};
+
+    use ipld_core::ipld::Ipld;
+
+    use super::*;
+    use crate::Recipient;
+
+    fn jwe() -> JsonWebEncryption {
+        JsonWebEncryption {
+            aad: Some(Bytes::from(b"external".to_vec())),
+            ciphertext: Bytes::from((0..=255).cycle().take(70_000).collect::<Vec<u8>>()),
+            iv: Bytes::from([1; 24]),
+            protected: Bytes::from(br#"{"enc":"XC20P"}"#.to_vec()),
+            recipients: vec![Recipient {
+                encrypted_key: Some(Bytes::from([2; 32])),
+                header: BTreeMap::from([("alg".to_string(), Ipld::from("XOR"))]),
+            }],
+            tag: Bytes::from([3; 16]),
+            unprotected: BTreeMap::from([("typ".to_string(), Ipld::from("JWE"))]),
+        }
+    }
+
+    #[test]
+    fn read_ciphertext() {
+        let jwe = jwe();
+        let mut block = b"prefix".to_vec();
+        block.extend(DagJoseCodec::encode_to_vec(&jwe).unwrap());
+        block.extend(b"suffix");
+
+        let mut cursor = Cursor::new(block);
+        cursor.set_position(6);
+        let mut reader = JweReader::new(cursor).unwrap();
+        assert_eq!(reader.ciphertext_len(), 70_000);
+        assert!(reader.jwe().ciphertext.is_empty());
+        assert_eq!(reader.jwe().recipients, jwe.recipients);
+        assert_eq!(reader.jwe().unprotected, jwe.unprotected);
+        for _ in 0..2 {
+            let mut ciphertext = Vec::new();
+            reader
+                .ciphertext()
+                .unwrap()
+                .read_to_end(&mut ciphertext)
+                .unwrap();
+            assert_eq!(ciphertext, jwe.ciphertext.as_slice());
+        }
+        assert_eq!(reader.into_jwe().unwrap(), jwe);
+    }
+
+    #[test]
+    fn stream_ciphertext() {
+        let jwe = jwe();
+        let block = DagJoseCodec::encode_to_vec(&jwe).unwrap();
+        let without_ciphertext = JsonWebEncryption {
+            ciphertext: Bytes::default(),
+            ..jwe.clone()
+        };
+
+        let mut stream = JweStream::new(block.as_slice()).unwrap();
+        assert_eq!(stream.ciphertext_len(), 70_000);
+        let mut ciphertext = Vec::new();
+        stream.read_to_end(&mut ciphertext).unwrap();
+        assert_eq!(ciphertext, jwe.ciphertext.as_slice());
+        assert_eq!(stream.finish().unwrap(), without_ciphertext);
+
+        // Unread ciphertext is skipped.
+        let mut stream = JweStream::new(block.as_slice()).unwrap();
+        let mut start = [0; 100];
+        stream.read_exact(&mut start).unwrap();
+        assert_eq!(start, jwe.ciphertext[..100]);
+        assert_eq!(stream.finish().unwrap(), without_ciphertext);
+
+        let stream = JweStream::new(&block[..block.len() - 1]).unwrap();
+        assert!(matches!(stream.finish(), Err(Error::Io(_))));
+    }
+
+    #[test]
+    fn reject_invalid_blocks() {
+        let block = DagJoseCodec::encode_to_vec(&jwe()).unwrap();
+        assert!(matches!(
+            JweReader::new(Cursor::new(&block[..block.len() - 1])),
+            Err(Error::Io(_))
+        ));
+        // Without recipients and unprotected header the ciphertext is the last field, its
+        // truncation is found when reading it.
+        let block = DagJoseCodec::encode_to_vec(&JsonWebEncryption {
+            recipients: vec![],
+            unprotected: BTreeMap::new(),
+            ..jwe()
+        })
+        .unwrap();
+        let reader = JweReader::new(Cursor::new(&block[..block.len() - 1])).unwrap();
+        assert!(matches!(reader.into_jwe(), Err(Error::Io(_))));
+
+        let jws = DagJoseCodec::encode_to_vec(&Ipld::Map(BTreeMap::from([(
+            "payload".to_string(),
+            Ipld::Bytes(vec![1]),
+        )])))
+        .unwrap();
+        assert!(matches!(
+            JweReader::new(Cursor::new(jws)),
+            Err(Error::NotJwe)
+        ));
+    }
+
+    #[test]
+    fn encode_from_reader() {
+        let jwe = jwe();
+        let mut block = Vec::new();
+        let fields = JsonWebEncryption {
+            ciphertext: Bytes::default(),
+            ..jwe.clone()
+        };
+        DagJoseCodec::encode_jwe_from_reader(
+            &mut block,
+            &fields,
+            jwe.ciphertext.as_slice(),
+            jwe.ciphertext.len() as u64,
+        )
+        .unwrap();
+        assert_eq!(block, DagJoseCodec::encode_to_vec(&jwe).unwrap());
+
+        assert!(matches!(
+            DagJoseCodec::encode_jwe_from_reader(
+                &mut Vec::new(),
+                &fields,
+                &jwe.ciphertext[1..],
+                jwe.ciphertext.len() as u64,
+            ),
+            Err(Error::Io(_))
+        ));
+    }
+
+    #[cfg(feature = "jwe")]
+    #[test]
+    fn decrypt_streamed() {
+        use crate::{ContentEncryption, DirectKey, JweBuilder};
+
+        let key = DirectKey::new(&[7; 32]);
+        let plaintext = vec![42; 100_000];
+        let jwe = JweBuilder::new(ContentEncryption::XC20P)
+            .recipient(&key)
+            .build(&plaintext)
+            .unwrap();
+        let block = DagJoseCodec::encode_to_vec(&jwe).unwrap();
+        let mut reader = JweReader::new(Cursor::new(block)).unwrap();
+        assert_eq!(reader.decrypt(&key).unwrap(), plaintext);
+        assert!(matches!(
+            reader.decrypt(&DirectKey::new(&[8; 32])),
+            Err(Error::Decryption)
+        ));
+    }
+}
@@ -1,3 +1,4 @@
+//! Tests of the DAG-JOSE codec against the fixtures in `fixtures/dag-jose.md`.
 #![deny(missing_docs)]
 #![deny(warnings)]
 #![cfg(feature = "dag-json")]
@@ -100,8 +101,110 @@ test_fixture!(jws, "jws");
 test_fixture!(jws_signature_1, "jws-signature-1");
 test_fixture!(jws_signature_2, "jws-signature-2");
 test_fixture!(jws_signatures, "jws-signatures");
+test_fixture!(jws_ed25519, "jws-ed25519");
 test_fixture!(jwe_symmetric, "jwe-symmetric");
 test_fixture!(jwe_asymmetric, "jwe-asymmetric");
 test_fixture!(jwe_no_recipients, "jwe-no-recipients");
 test_fixture!(jwe_recipient, "jwe-recipient");
 test_fixture!(jwe_recipients, "jwe-recipients");
+
+// Decode the hex data of a fixture into a DAG-JOSE value.
+#[cfg(any(feature = "ed25519", feature = "es256k"))]
+fn decode_fixture(hex_name: &str) -> Jose {
+    let fixtures = match FIXTURES.lock() {
+        Ok(f) => f,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    let dag_jose_hex = remove_whitespace(fixtures.must_find_hunk(hex_name).data())
+        .expect("hex fixture data should be UTF8");
+    DagJoseCodec::decode_from_slice(
+        &hex::decode(dag_jose_hex).expect("hex fixture data should be hex encoded"),
+    )
+    .expect("hex fixture data should represent a DAG-JOSE value")
+}
+
+// Decode the hex data of a fixture hunk into bytes.
+#[cfg(feature = "ed25519")]
+fn fixture_bytes(hex_name: &str) -> Vec<u8> {
+    let fixtures = match FIXTURES.lock() {
+        Ok(f) => f,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    let data = remove_whitespace(fixtures.must_find_hunk(hex_name).data())
+        .expect("hex fixture data should be UTF8");
+    hex::decode(data).expect("hex fixture data should be hex encoded")
+}
+
+// Verify the Ed25519 fixture with its public key and reproduce it byte for byte by signing
+// with its private key.
+#[cfg(feature = "ed25519")]
+#[test]
+fn jws_ed25519_sign_verify() {
+    use dag_jose::{Ed25519Signer, Ed25519Verifier, ProtectedHeader};
+
+    let secret = fixture_bytes("jws-ed25519/private-key.hex");
+    let signer = Ed25519Signer::from_bytes(&secret.try_into().expect("key should be 32 bytes"));
+    let public = fixture_bytes("jws-ed25519/public-key.hex");
+    let verifier = Ed25519Verifier::from_bytes(&public.try_into().expect("key should be 32 bytes"))
+        .expect("key should be a valid Ed25519 key");
+
+    let Jose::Signature(fixture) = decode_fixture("jws-ed25519/serial.dag-jose.hex") else {
+        panic!("fixture should be a JWS value")
+    };
+    assert!(fixture.verify(&[&verifier])[0].is_ok());
+    assert!(fixture.verify(&[&signer.verifier()])[0].is_ok());
+
+    // The base JWS fixture has the same payload and header but a key that is not published.
+    let Jose::Signature(other) = decode_fixture("jws/serial.dag-jose.hex") else {
+        panic!("fixture should be a JWS value")
+    };
+    assert!(other.verify(&[&verifier])[0].is_err());
+
+    // Ed25519 signatures are deterministic so signing reproduces the fixture.
+    let mut jws = fixture.clone();
+    jws.signatures.clear();
+    jws.sign(&signer, ProtectedHeader::default())
+        .expect("signing should succeed");
+    assert_eq!(jws, fixture);
+    assert_eq!(
+        DagJoseCodec::encode_to_vec(&jws).expect("JWS should encode"),
+        fixture_bytes("jws-ed25519/serial.dag-jose.hex"),
+    );
+}
+
+// Verify the ES256K fixtures and reproduce the low S signature with the fixture key.
+#[cfg(feature = "es256k")]
+#[test]
+fn jws_es256k_sign_verify() {
+    use dag_jose::{Error, Es256kSigner, ProtectedHeader};
+
+    let secret = hex::decode("278a5de700e29faae8e40e366ec5012b5ec63d36ec77e8a2417154cc1d25383f")
+        .expect("key should be hex encoded");
+    let signer = Es256kSigner::from_bytes(&secret.try_into().expect("key should be 32 bytes"))
+        .expect("key should be a valid secp256k1 key");
+    let verifier = signer.verifier();
+
+    let Jose::Signature(low_s) = decode_fixture("jws-signature-1/serial.dag-jose.hex") else {
+        panic!("fixture should be a JWS value")
+    };
+    assert!(low_s.verify(&[&verifier])[0].is_ok());
+
+    let Jose::Signature(high_s) = decode_fixture("jws-signature-2/serial.dag-jose.hex") else {
+        panic!("fixture should be a JWS value")
+    };
+    assert!(matches!(
+        high_s.verify(&[&verifier])[0],
+        Err(Error::HighSSignature)
+    ));
+
+    // ECDSA signatures are deterministic so signing reproduces the low S fixture.
+    let mut jws = low_s.clone();
+    jws.signatures.clear();
+    jws.sign(&signer, ProtectedHeader::default())
+        .expect("signing should succeed");
+    assert_eq!(jws, low_s);
+    assert_eq!(
+        DagJoseCodec::encode_to_vec(&jws).expect("JWS should encode"),
+        DagJoseCodec::encode_to_vec(&low_s).expect("JWS should encode"),
+    );
+}